@@ -6,7 +6,7 @@ use rand_core::{CryptoRng, RngCore};
 
 use num_iter::range;
 
-use crate::keys::ElgamalGroupElements;
+use crate::group::Group;
 use crate::ElgamalGroup;
 
 pub(crate) fn generate_prime<R: RngCore + CryptoRng>(rng: &mut R, bit_size: usize) -> BigUint {
@@ -53,13 +53,21 @@ pub(crate) fn elgamal_parameter_generation_type1<R: RngCore + CryptoRng>(
     }
 }
 
+/// Key generation in any [`Group`]: returns `(y, x)` with `y = g^x`.
+pub fn group_key_generation<G: Group, R: RngCore + CryptoRng>(
+    rng: &mut R,
+    group: &G,
+) -> (G::Element, G::Scalar) {
+    let x = group.random_scalar(rng);
+
+    (group.exp_generator(&x), x)
+}
+
 pub fn key_generation<R: RngCore + CryptoRng>(
     rng: &mut R,
     group: &ElgamalGroup,
 ) -> (BigUint, BigUint) {
-    let x = rng.gen_biguint_range(&BigUint::one(), group.get_q());
-
-    (group.get_g().modpow(&x, group.get_p()), x)
+    group_key_generation(rng, group)
 }
 
 #[cfg(test)]
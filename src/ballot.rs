@@ -0,0 +1,361 @@
+use digest::DynDigest;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::{Error, Result};
+use crate::group::Group;
+use crate::internal::group_ciphertext_mul;
+use crate::proofs::{challenge, DleqProof};
+
+/// Cramer–Damgård–Schoenmakers proof that an exponential El Gamal ciphertext
+/// `(a, b) = (g^r, g^v * y^r)` encrypts one of a public set of values.
+///
+/// There is one branch per value `v_j`, each a Chaum–Pedersen proof of
+/// `log_g a = log_y (b / g^v_j)`; all but the true branch are simulated and the
+/// challenges must add up to the Fiat–Shamir challenge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisjunctiveProof<G: Group> {
+    /// Challenge of every branch
+    pub challenges: Vec<G::Scalar>,
+    /// Response of every branch
+    pub responses: Vec<G::Scalar>,
+}
+
+/// Proof that every ciphertext of a ballot encrypts 0 or 1 and that exactly
+/// `k` of them encrypt 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BallotProof<G: Group> {
+    /// 0/1 proof of every choice
+    pub choices: Vec<DisjunctiveProof<G>>,
+    /// Proof that the product of all ciphertexts encrypts `k`
+    pub total: DisjunctiveProof<G>,
+}
+
+const DISJUNCTIVE_LABEL: &[u8] = b"elgamal disjunctive proof";
+
+impl<G: Group> DisjunctiveProof<G> {
+    /// Proves that `(a, b)`, encrypted under `y` with randomness `r`, encrypts
+    /// `values[index]`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        group: &G,
+        digest: &mut dyn DynDigest,
+        y: &G::Element,
+        (a, b): (&G::Element, &G::Element),
+        r: &G::Scalar,
+        values: &[G::Scalar],
+        index: usize,
+    ) -> Result<Self> {
+        if index >= values.len() {
+            return Err(Error::InvalidData);
+        }
+
+        let g = group.generator();
+        let quotients = Self::quotients(group, b, values)?;
+        let mut challenges = Vec::with_capacity(values.len());
+        let mut responses = Vec::with_capacity(values.len());
+        let mut commitments = Vec::with_capacity(2 * values.len());
+
+        let w = group.random_scalar(rng);
+        for (j, quotient) in quotients.iter().enumerate() {
+            if j == index {
+                challenges.push(group.scalar_from_u64(0));
+                responses.push(group.scalar_from_u64(0));
+                commitments.push(group.exp_generator(&w));
+                commitments.push(group.exp(y, &w));
+            } else {
+                let c = group.random_scalar(rng);
+                let s = group.random_scalar(rng);
+                commitments.push(DleqProof::commitment(group, &g, a, &c, &s)?);
+                commitments.push(DleqProof::commitment(group, y, quotient, &c, &s)?);
+                challenges.push(c);
+                responses.push(s);
+            }
+        }
+
+        let c = Self::challenge(group, digest, y, a, b, &quotients, &commitments);
+        let simulated = challenges
+            .iter()
+            .fold(group.scalar_from_u64(0), |acc, c| group.scalar_add(&acc, c));
+        challenges[index] = group.scalar_sub(&c, &simulated);
+        responses[index] = group.scalar_add(&w, &group.scalar_mul(&challenges[index], r));
+
+        Ok(Self {
+            challenges,
+            responses,
+        })
+    }
+
+    /// Verifies that `(a, b)` encrypts one of `values` under `y`.
+    pub fn verify(
+        &self,
+        group: &G,
+        digest: &mut dyn DynDigest,
+        y: &G::Element,
+        (a, b): (&G::Element, &G::Element),
+        values: &[G::Scalar],
+    ) -> Result<()> {
+        if values.is_empty()
+            || self.challenges.len() != values.len()
+            || self.responses.len() != values.len()
+        {
+            return Err(Error::InvalidData);
+        }
+        if !group.is_element(y) || !group.is_element(a) || !group.is_element(b) {
+            return Err(Error::InvalidData);
+        }
+
+        let g = group.generator();
+        let quotients = Self::quotients(group, b, values)?;
+        let mut commitments = Vec::with_capacity(2 * values.len());
+        for ((quotient, c), s) in quotients.iter().zip(&self.challenges).zip(&self.responses) {
+            commitments.push(DleqProof::commitment(group, &g, a, c, s)?);
+            commitments.push(DleqProof::commitment(group, y, quotient, c, s)?);
+        }
+
+        let c = Self::challenge(group, digest, y, a, b, &quotients, &commitments);
+        let sum = self
+            .challenges
+            .iter()
+            .fold(group.scalar_from_u64(0), |acc, c| group.scalar_add(&acc, c));
+
+        match c == sum {
+            true => Ok(()),
+            false => Err(Error::Verification),
+        }
+    }
+
+    /// Proves that `(a, b)` encrypts `bit`, which must be 0 or 1.
+    pub fn prove_bit<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        group: &G,
+        digest: &mut dyn DynDigest,
+        y: &G::Element,
+        ciphertext: (&G::Element, &G::Element),
+        r: &G::Scalar,
+        bit: bool,
+    ) -> Result<Self> {
+        let values = Self::bits(group);
+
+        Self::prove(rng, group, digest, y, ciphertext, r, &values, bit as usize)
+    }
+
+    /// Verifies that `(a, b)` encrypts 0 or 1.
+    pub fn verify_bit(
+        &self,
+        group: &G,
+        digest: &mut dyn DynDigest,
+        y: &G::Element,
+        ciphertext: (&G::Element, &G::Element),
+    ) -> Result<()> {
+        self.verify(group, digest, y, ciphertext, &Self::bits(group))
+    }
+
+    fn bits(group: &G) -> [G::Scalar; 2] {
+        [group.scalar_from_u64(0), group.scalar_from_u64(1)]
+    }
+
+    /// `b / g^v_j` for every value.
+    fn quotients(group: &G, b: &G::Element, values: &[G::Scalar]) -> Result<Vec<G::Element>> {
+        values
+            .iter()
+            .map(|v| {
+                let divider = group
+                    .invert(&group.exp_generator(v))
+                    .ok_or(Error::InvalidInverse)?;
+                Ok(group.mul(b, &divider))
+            })
+            .collect()
+    }
+
+    fn challenge(
+        group: &G,
+        digest: &mut dyn DynDigest,
+        y: &G::Element,
+        a: &G::Element,
+        b: &G::Element,
+        quotients: &[G::Element],
+        commitments: &[G::Element],
+    ) -> G::Scalar {
+        let mut elements = vec![y, a, b];
+        elements.extend(quotients);
+        elements.extend(commitments);
+
+        challenge(group, digest, DISJUNCTIVE_LABEL, &elements)
+    }
+}
+
+impl<G: Group> BallotProof<G> {
+    /// Proves that every `ciphertexts[i]`, encrypted with `randomness[i]`, encrypts
+    /// `choices[i]` in {0, 1} and that exactly `k` choices are set.
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        group: &G,
+        digest: &mut dyn DynDigest,
+        y: &G::Element,
+        ciphertexts: &[(G::Element, G::Element)],
+        randomness: &[G::Scalar],
+        choices: &[bool],
+        k: u64,
+    ) -> Result<Self> {
+        if ciphertexts.is_empty()
+            || ciphertexts.len() != randomness.len()
+            || ciphertexts.len() != choices.len()
+        {
+            return Err(Error::InvalidData);
+        }
+
+        let proofs = ciphertexts
+            .iter()
+            .zip(randomness)
+            .zip(choices)
+            .map(|(((a, b), r), bit)| {
+                DisjunctiveProof::prove_bit(rng, group, digest, y, (a, b), r, *bit)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let (a, b) = Self::sum(group, ciphertexts);
+        let r = randomness
+            .iter()
+            .fold(group.scalar_from_u64(0), |acc, r| group.scalar_add(&acc, r));
+        let total = DisjunctiveProof::prove(
+            rng,
+            group,
+            digest,
+            y,
+            (&a, &b),
+            &r,
+            &[group.scalar_from_u64(k)],
+            0,
+        )?;
+
+        Ok(Self {
+            choices: proofs,
+            total,
+        })
+    }
+
+    /// Verifies that every ciphertext encrypts 0 or 1 and that `k` of them encrypt 1.
+    pub fn verify(
+        &self,
+        group: &G,
+        digest: &mut dyn DynDigest,
+        y: &G::Element,
+        ciphertexts: &[(G::Element, G::Element)],
+        k: u64,
+    ) -> Result<()> {
+        if ciphertexts.is_empty() || ciphertexts.len() != self.choices.len() {
+            return Err(Error::InvalidData);
+        }
+
+        for ((a, b), proof) in ciphertexts.iter().zip(&self.choices) {
+            proof.verify_bit(group, digest, y, (a, b))?;
+        }
+
+        let (a, b) = Self::sum(group, ciphertexts);
+        self.total
+            .verify(group, digest, y, (&a, &b), &[group.scalar_from_u64(k)])
+    }
+
+    /// Homomorphic sum of the choices.
+    fn sum(group: &G, ciphertexts: &[(G::Element, G::Element)]) -> (G::Element, G::Element) {
+        ciphertexts.iter().fold(
+            (group.identity(), group.identity()),
+            |(a1, b1), (a2, b2)| group_ciphertext_mul(group, (&a1, &b1), (a2, b2)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{prelude::StdRng, SeedableRng};
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::internal::group_encrypt_exponent_with;
+    use crate::{ElgamalGroup, Ristretto255};
+
+    fn disjunctive<G: Group>(group: &G) {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+        let y = group.exp_generator(&group.random_scalar(&mut rng));
+
+        let values: Vec<_> = [3, 5, 8]
+            .iter()
+            .map(|v| group.scalar_from_u64(*v))
+            .collect();
+        let r = group.random_scalar(&mut rng);
+        let (a, b) = group_encrypt_exponent_with(group, &y, &values[1], &r);
+        let proof =
+            DisjunctiveProof::prove(&mut rng, group, &mut digest, &y, (&a, &b), &r, &values, 1)
+                .unwrap();
+        proof
+            .verify(group, &mut digest, &y, (&a, &b), &values)
+            .unwrap();
+
+        // A proof claiming the wrong value does not verify.
+        let proof =
+            DisjunctiveProof::prove(&mut rng, group, &mut digest, &y, (&a, &b), &r, &values, 2)
+                .unwrap();
+        assert!(proof
+            .verify(group, &mut digest, &y, (&a, &b), &values)
+            .is_err());
+
+        // Bits.
+        let (a, b) = group_encrypt_exponent_with(group, &y, &group.scalar_from_u64(2), &r);
+        let proof =
+            DisjunctiveProof::prove_bit(&mut rng, group, &mut digest, &y, (&a, &b), &r, true)
+                .unwrap();
+        assert!(proof.verify_bit(group, &mut digest, &y, (&a, &b)).is_err());
+    }
+
+    #[test]
+    fn disjunctive_proof() {
+        let mut rng = StdRng::from_entropy();
+        disjunctive(&ElgamalGroup::generate(&mut rng, 256, 128));
+        disjunctive(&Ristretto255);
+    }
+
+    #[test]
+    fn ballot_proof() {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+        let group = Ristretto255;
+        let y = group.exp_generator(&group.random_scalar(&mut rng));
+
+        let choices = [false, true, false, true];
+        let randomness: Vec<_> = choices
+            .iter()
+            .map(|_| group.random_scalar(&mut rng))
+            .collect();
+        let ciphertexts: Vec<_> = choices
+            .iter()
+            .zip(&randomness)
+            .map(|(bit, r)| {
+                group_encrypt_exponent_with(&group, &y, &group.scalar_from_u64(*bit as u64), r)
+            })
+            .collect();
+
+        let proof = BallotProof::prove(
+            &mut rng,
+            &group,
+            &mut digest,
+            &y,
+            &ciphertexts,
+            &randomness,
+            &choices,
+            2,
+        )
+        .unwrap();
+        proof
+            .verify(&group, &mut digest, &y, &ciphertexts, 2)
+            .unwrap();
+        assert!(proof
+            .verify(&group, &mut digest, &y, &ciphertexts, 1)
+            .is_err());
+        assert!(proof
+            .verify(&group, &mut digest, &y, &ciphertexts[1..], 2)
+            .is_err());
+    }
+}
@@ -0,0 +1,415 @@
+use std::collections::BTreeMap;
+
+use digest::DynDigest;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::{Error, Result};
+use crate::group::Group;
+use crate::internal::{group_sign, group_verify};
+use crate::transcript::Transcript;
+
+/// El Gamal signature `(r, s)` of a message by its sender's long-term key.
+pub type BdSignature<G> = (<G as Group>::Element, <G as Group>::Scalar);
+
+/// Messages of the Burmester–Desmedt group key agreement, all broadcast.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BdMessage<G: Group> {
+    /// `z_i = g^r_i`
+    Round1 {
+        sender: u32,
+        z: G::Element,
+        signature: Option<BdSignature<G>>,
+    },
+    /// `X_i = (z_(i+1) / z_(i-1))^r_i`
+    Round2 {
+        sender: u32,
+        x: G::Element,
+        signature: Option<BdSignature<G>>,
+    },
+}
+
+/// Phases of the group key agreement, see [`BdParticipant::handle`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BdPhase {
+    /// Waiting for `z_j` of every party
+    Round1,
+    /// Waiting for `X_j` of every party
+    Round2,
+    /// The group key is known
+    Finished,
+}
+
+/// Long-term keys authenticating every message of the agreement.
+#[derive(Debug, Clone)]
+struct Authentication<G: Group> {
+    session: Vec<u8>,
+    x: G::Scalar,
+    /// Public keys of all parties, party `i` at position `i - 1`
+    keys: Vec<G::Element>,
+}
+
+/// Party `i` of an `n`-party Burmester–Desmedt key agreement, with parties arranged
+/// in a ring.
+///
+/// The agreed key is `g^(r_1 r_2 + r_2 r_3 + ... + r_n r_1)`. The plain protocol is
+/// secure against passive adversaries only; [`authenticated`] signs every message
+/// with the long-term key of its sender, bound to a session identifier.
+///
+/// [`authenticated`]: BdParticipant::authenticated
+#[derive(Debug, Clone)]
+pub struct BdParticipant<G: Group> {
+    group: G,
+    index: u32,
+    parties: u32,
+    r: G::Scalar,
+    authentication: Option<Authentication<G>>,
+    phase: BdPhase,
+    z: BTreeMap<u32, G::Element>,
+    x: BTreeMap<u32, G::Element>,
+    key: Option<G::Element>,
+}
+
+const BD_LABEL: &[u8] = b"elgamal burmester-desmedt";
+
+/// Scalar signed for a message: hash of the session, round, sender and value.
+fn message_hash<G: Group>(
+    group: &G,
+    digest: &mut dyn DynDigest,
+    session: &[u8],
+    round: u8,
+    sender: u32,
+    value: &G::Element,
+) -> G::Scalar {
+    let mut transcript = Transcript::new(digest, BD_LABEL);
+    transcript.append_message(b"session", session);
+    transcript.append_message(b"round", &[round]);
+    transcript.append_message(b"sender", &sender.to_be_bytes());
+    transcript.append_element(group, b"value", value);
+
+    transcript.challenge_scalar(group, b"h")
+}
+
+impl<G: Group> BdParticipant<G> {
+    /// Creates party `index` (from 1 to `parties`) of an unauthenticated agreement.
+    pub fn new<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        group: &G,
+        index: u32,
+        parties: u32,
+    ) -> Result<Self> {
+        if parties < 2 || index == 0 || index > parties {
+            return Err(Error::InvalidData);
+        }
+
+        let r = group.random_scalar(rng);
+        let mut z = BTreeMap::new();
+        z.insert(index, group.exp_generator(&r));
+
+        Ok(Self {
+            group: group.clone(),
+            index,
+            parties,
+            r,
+            authentication: None,
+            phase: BdPhase::Round1,
+            z,
+            x: BTreeMap::new(),
+            key: None,
+        })
+    }
+
+    /// Creates party `index` of an agreement authenticated with the long-term key
+    /// `x`; `keys` holds the public keys of all parties, in order.
+    pub fn authenticated<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        group: &G,
+        index: u32,
+        session: &[u8],
+        x: G::Scalar,
+        keys: Vec<G::Element>,
+    ) -> Result<Self> {
+        let mut participant = Self::new(rng, group, index, keys.len() as u32)?;
+        if keys[index as usize - 1] != group.exp_generator(&x) {
+            return Err(Error::InvalidPrivateKey);
+        }
+        participant.authentication = Some(Authentication {
+            session: session.to_vec(),
+            x,
+            keys,
+        });
+
+        Ok(participant)
+    }
+
+    /// Returns the index of the party.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns the current phase.
+    pub fn phase(&self) -> BdPhase {
+        self.phase
+    }
+
+    fn sign<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        digest: &mut dyn DynDigest,
+        round: u8,
+        value: &G::Element,
+    ) -> Result<Option<BdSignature<G>>> {
+        let auth = match self.authentication {
+            Some(ref auth) => auth,
+            None => return Ok(None),
+        };
+        let h = message_hash(&self.group, digest, &auth.session, round, self.index, value);
+
+        Ok(Some(group_sign(rng, &self.group, &auth.x, &h)?))
+    }
+
+    fn verify(
+        &self,
+        digest: &mut dyn DynDigest,
+        round: u8,
+        sender: u32,
+        value: &G::Element,
+        signature: &Option<BdSignature<G>>,
+    ) -> Result<()> {
+        let auth = match self.authentication {
+            Some(ref auth) => auth,
+            None => return Ok(()),
+        };
+        let (r, s) = signature.as_ref().ok_or(Error::Verification)?;
+        if !self.group.is_element(r) {
+            return Err(Error::Verification);
+        }
+        let h = message_hash(&self.group, digest, &auth.session, round, sender, value);
+
+        group_verify(&self.group, &auth.keys[sender as usize - 1], &h, r, s)
+    }
+
+    /// First message of the party, to broadcast.
+    pub fn start<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        digest: &mut dyn DynDigest,
+    ) -> Result<BdMessage<G>> {
+        let z = self.z[&self.index].clone();
+
+        Ok(BdMessage::Round1 {
+            sender: self.index,
+            signature: self.sign(rng, digest, 1, &z)?,
+            z,
+        })
+    }
+
+    /// Processes a message broadcast by another party. Returns the second message of
+    /// the party once every `z_j` is known; the key is computed once every `X_j`
+    /// is known. Messages of the second round may arrive before the first round is
+    /// complete.
+    pub fn handle<R: RngCore + CryptoRng>(
+        &mut self,
+        rng: &mut R,
+        digest: &mut dyn DynDigest,
+        message: BdMessage<G>,
+    ) -> Result<Option<BdMessage<G>>> {
+        if self.phase == BdPhase::Finished {
+            return Err(Error::InvalidState);
+        }
+
+        let (round, sender, value, signature) = match message {
+            BdMessage::Round1 {
+                sender,
+                z,
+                signature,
+            } => (1, sender, z, signature),
+            BdMessage::Round2 {
+                sender,
+                x,
+                signature,
+            } => (2, sender, x, signature),
+        };
+        if sender == 0 || sender > self.parties || sender == self.index {
+            return Err(Error::InvalidData);
+        }
+        if !self.group.is_element(&value) || (round == 1 && value == self.group.identity()) {
+            return Err(Error::InvalidData);
+        }
+        self.verify(digest, round, sender, &value, &signature)?;
+
+        let received = match round {
+            1 => &mut self.z,
+            _ => &mut self.x,
+        };
+        if matches!(received.get(&sender), Some(previous) if *previous != value) {
+            return Err(Error::InvalidData);
+        }
+        received.insert(sender, value);
+
+        let mut reply = None;
+        if self.phase == BdPhase::Round1 && self.z.len() == self.parties as usize {
+            let x = self.second_round()?;
+            reply = Some(BdMessage::Round2 {
+                sender: self.index,
+                signature: self.sign(rng, digest, 2, &x)?,
+                x: x.clone(),
+            });
+            self.x.insert(self.index, x);
+            self.phase = BdPhase::Round2;
+        }
+        if self.phase == BdPhase::Round2 && self.x.len() == self.parties as usize {
+            self.key = Some(self.compute_key());
+            self.phase = BdPhase::Finished;
+        }
+
+        Ok(reply)
+    }
+
+    /// Index of the party at offset `offset` along the ring.
+    fn neighbour(&self, offset: i64) -> u32 {
+        let n = self.parties as i64;
+        ((self.index as i64 - 1 + offset).rem_euclid(n) + 1) as u32
+    }
+
+    /// `X_i = (z_(i+1) / z_(i-1))^r_i`
+    fn second_round(&self) -> Result<G::Element> {
+        let group = &self.group;
+        let previous = group
+            .invert(&self.z[&self.neighbour(-1)])
+            .ok_or(Error::InvalidInverse)?;
+        let quotient = group.mul(&self.z[&self.neighbour(1)], &previous);
+
+        Ok(group.exp(&quotient, &self.r))
+    }
+
+    /// `K = z_(i-1)^(n r_i) X_i^(n-1) X_(i+1)^(n-2) ... X_(i+n-2)`
+    fn compute_key(&self) -> G::Element {
+        let group = &self.group;
+        let n = self.parties as u64;
+        let e = group.scalar_mul(&group.scalar_from_u64(n), &self.r);
+
+        (0..n - 1).fold(group.exp(&self.z[&self.neighbour(-1)], &e), |key, j| {
+            let x = &self.x[&self.neighbour(j as i64)];
+            group.mul(&key, &group.exp(x, &group.scalar_from_u64(n - 1 - j)))
+        })
+    }
+
+    /// Returns the agreed group element once the protocol is finished.
+    pub fn key(&self) -> Result<&G::Element> {
+        self.key.as_ref().ok_or(Error::InvalidState)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{prelude::StdRng, SeedableRng};
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::{ElgamalGroup, Ristretto255};
+
+    /// Broadcasts every message until no party has anything left to send.
+    fn run<G: Group>(parties: &mut [BdParticipant<G>]) -> Result<()> {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+        let mut queue = parties
+            .iter()
+            .map(|p| p.start(&mut rng, &mut digest))
+            .collect::<Result<Vec<_>>>()?;
+
+        while let Some(message) = queue.pop() {
+            let sender = match message {
+                BdMessage::Round1 { sender, .. } | BdMessage::Round2 { sender, .. } => sender,
+            };
+            for party in parties.iter_mut().filter(|p| p.index() != sender) {
+                if let Some(reply) = party.handle(&mut rng, &mut digest, message.clone())? {
+                    queue.insert(0, reply);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn agreement<G: Group>(group: &G) {
+        let mut rng = StdRng::from_entropy();
+        for n in [2, 3, 5] {
+            let mut parties: Vec<_> = (1..=n)
+                .map(|i| BdParticipant::new(&mut rng, group, i, n).unwrap())
+                .collect();
+            run(&mut parties).unwrap();
+
+            let key = parties[0].key().unwrap().clone();
+            let r: Vec<_> = parties.iter().map(|p| p.r.clone()).collect();
+            let exponent = (0..n as usize).fold(group.scalar_from_u64(0), |acc, i| {
+                group.scalar_add(&acc, &group.scalar_mul(&r[i], &r[(i + 1) % n as usize]))
+            });
+            assert_eq!(key, group.exp_generator(&exponent));
+            assert!(parties.iter().all(|p| *p.key().unwrap() == key));
+        }
+    }
+
+    #[test]
+    fn burmester_desmedt() {
+        let mut rng = StdRng::from_entropy();
+        agreement(&ElgamalGroup::generate(&mut rng, 256, 128));
+        agreement(&Ristretto255);
+    }
+
+    #[test]
+    fn authenticated_agreement() {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+        let group = Ristretto255;
+        let secrets: Vec<_> = (0..4).map(|_| group.random_scalar(&mut rng)).collect();
+        let keys: Vec<_> = secrets.iter().map(|x| group.exp_generator(x)).collect();
+
+        let mut parties: Vec<_> = secrets
+            .iter()
+            .enumerate()
+            .map(|(i, x)| {
+                BdParticipant::authenticated(
+                    &mut rng,
+                    &group,
+                    i as u32 + 1,
+                    b"session 1",
+                    *x,
+                    keys.clone(),
+                )
+                .unwrap()
+            })
+            .collect();
+        run(&mut parties).unwrap();
+        let key = parties[0].key().unwrap();
+        assert!(parties.iter().all(|p| p.key().unwrap() == key));
+
+        // A message signed for another session, or by an outsider, is rejected.
+        let other = BdParticipant::authenticated(
+            &mut rng,
+            &group,
+            1,
+            b"session 2",
+            secrets[0],
+            keys.clone(),
+        )
+        .unwrap();
+        let mut target = BdParticipant::authenticated(
+            &mut rng,
+            &group,
+            2,
+            b"session 1",
+            secrets[1],
+            keys.clone(),
+        )
+        .unwrap();
+        let message = other.start(&mut rng, &mut digest).unwrap();
+        assert!(matches!(
+            target.handle(&mut rng, &mut digest, message),
+            Err(Error::Verification)
+        ));
+        let unsigned = BdParticipant::new(&mut rng, &group, 1, 4).unwrap();
+        let message = unsigned.start(&mut rng, &mut digest).unwrap();
+        assert!(target.handle(&mut rng, &mut digest, message).is_err());
+        assert!(other.key().is_err());
+    }
+}
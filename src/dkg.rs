@@ -0,0 +1,849 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::{Error, Result};
+use crate::group::Group;
+use crate::sharing::{lagrange_coefficient, verify_share, Polynomial};
+use crate::threshold::{ThresholdKeyShare, ThresholdPublicKey};
+
+/// Messages exchanged during the distributed key generation.
+///
+/// `Share` messages must be sent over a private authenticated channel to `recipient`,
+/// every other message must be broadcast to all parties.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DkgMessage<G: Group> {
+    /// Feldman commitments `g^a_k` to the polynomial of `dealer`
+    Commitments {
+        dealer: u32,
+        commitments: Vec<G::Element>,
+    },
+    /// Private share `f_dealer(recipient)`
+    Share {
+        dealer: u32,
+        recipient: u32,
+        share: G::Scalar,
+    },
+    /// `accuser` did not receive a valid share from `dealer`
+    Complaint { dealer: u32, accuser: u32 },
+    /// Share of `recipient` revealed by `dealer` in answer to a complaint
+    Justification {
+        dealer: u32,
+        recipient: u32,
+        share: G::Scalar,
+    },
+}
+
+/// Phases of the distributed key generation, see [`DkgParticipant::advance`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DkgPhase {
+    /// Commitments and shares are being distributed
+    Sharing,
+    /// Complaints against dealers are being broadcast and answered
+    Complaining,
+    /// Disqualified dealers are known, the key share can be computed
+    Finished,
+}
+
+/// What the shared secret of a session is made of.
+#[derive(Debug, Clone)]
+enum Mode<G: Group> {
+    /// Fresh key: sum of the random secrets of all qualified dealers
+    Generate,
+    /// Same key, new shares: every dealer shares zero and the result is added to `share`
+    Refresh {
+        previous: ThresholdPublicKey<G>,
+        share: G::Scalar,
+    },
+    /// Same key, new committee: every dealer shares its own share of `previous`
+    Reshare { previous: ThresholdPublicKey<G> },
+}
+
+/// Party of the Pedersen (Joint-Feldman) distributed key generation.
+///
+/// Every dealer shares a secret with Feldman verifiable secret sharing; the joint
+/// private key is built from the secrets of all qualified dealers and is never held by
+/// anyone. The protocol is driven by feeding every received message to [`handle`]
+/// and calling [`advance`] once all messages of the current phase have been received
+/// (or its deadline has passed).
+///
+/// The same state machine refreshes the shares of an existing key ([`refresh`]) and
+/// hands an existing key over to a new committee ([`reshare`]); in both cases the
+/// joint public key `y` is left unchanged.
+///
+/// [`handle`]: DkgParticipant::handle
+/// [`advance`]: DkgParticipant::advance
+/// [`refresh`]: DkgParticipant::refresh
+/// [`reshare`]: DkgParticipant::reshare
+#[derive(Debug, Clone)]
+pub struct DkgParticipant<G: Group> {
+    group: G,
+    mode: Mode<G>,
+    /// Index and polynomial of the party as a dealer
+    dealer: Option<(u32, Polynomial<G>)>,
+    /// Index of the party as a receiver of shares
+    receiver: Option<u32>,
+    /// Number of shares required to use the resulting key
+    threshold: usize,
+    /// Number of dealers
+    dealers: u32,
+    /// Number of receivers
+    parties: u32,
+    phase: DkgPhase,
+    commitments: BTreeMap<u32, Vec<G::Element>>,
+    shares: BTreeMap<u32, G::Scalar>,
+    complaints: BTreeSet<(u32, u32)>,
+    justified: BTreeSet<(u32, u32)>,
+    /// Justifications received before leaving [`DkgPhase::Sharing`]
+    pending: BTreeMap<(u32, u32), G::Scalar>,
+    disqualified: BTreeSet<u32>,
+}
+
+impl<G: Group> DkgParticipant<G> {
+    fn with_roles(
+        group: &G,
+        mode: Mode<G>,
+        dealer: Option<(u32, Polynomial<G>)>,
+        receiver: Option<u32>,
+        threshold: usize,
+        dealers: u32,
+        parties: u32,
+    ) -> Result<Self> {
+        if threshold == 0 || threshold > parties as usize {
+            return Err(Error::InvalidData);
+        }
+        if matches!(receiver, Some(index) if index == 0 || index > parties)
+            || matches!(dealer, Some((index, _)) if index == 0 || index > dealers)
+        {
+            return Err(Error::InvalidData);
+        }
+
+        let mut commitments = BTreeMap::new();
+        let mut shares = BTreeMap::new();
+        if let Some((index, ref polynomial)) = dealer {
+            commitments.insert(index, polynomial.commitments(group));
+            if let Some(receiver) = receiver {
+                shares.insert(index, polynomial.evaluate(group, receiver));
+            }
+        }
+
+        Ok(Self {
+            group: group.clone(),
+            mode,
+            dealer,
+            receiver,
+            threshold,
+            dealers,
+            parties,
+            phase: DkgPhase::Sharing,
+            commitments,
+            shares,
+            complaints: BTreeSet::new(),
+            justified: BTreeSet::new(),
+            pending: BTreeMap::new(),
+            disqualified: BTreeSet::new(),
+        })
+    }
+
+    /// Creates party `index` (from 1 to `parties`) of a `threshold`-of-`parties` key generation.
+    pub fn new<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        group: &G,
+        index: u32,
+        threshold: usize,
+        parties: u32,
+    ) -> Result<Self> {
+        let secret = group.random_scalar(rng);
+        let polynomial = Polynomial::random(rng, group, secret, threshold);
+
+        Self::with_roles(
+            group,
+            Mode::Generate,
+            Some((index, polynomial)),
+            Some(index),
+            threshold,
+            parties,
+            parties,
+        )
+    }
+
+    /// Proactive refresh: re-randomizes the share of every party of the committee of
+    /// `parties` without changing the joint key. Old shares must be erased afterwards.
+    pub fn refresh<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        share: &ThresholdKeyShare<G>,
+        parties: u32,
+    ) -> Result<Self> {
+        let previous = share.public().clone();
+        let group = previous.group().clone();
+        let zero = group.scalar_from_u64(0);
+        let polynomial = Polynomial::random(rng, &group, zero, previous.threshold());
+
+        Self::with_roles(
+            &group,
+            Mode::Refresh {
+                share: share.get_share().clone(),
+                previous: previous.clone(),
+            },
+            Some((share.index(), polynomial)),
+            Some(share.index()),
+            previous.threshold(),
+            parties,
+            parties,
+        )
+    }
+
+    /// Resharing by a member of the old committee of `previous_parties`: its share is
+    /// dealt to a new `threshold`-of-`parties` committee, in which the party takes index
+    /// `receiver` if it is also a member.
+    pub fn reshare<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        share: &ThresholdKeyShare<G>,
+        previous_parties: u32,
+        receiver: Option<u32>,
+        threshold: usize,
+        parties: u32,
+    ) -> Result<Self> {
+        let previous = share.public().clone();
+        let group = previous.group().clone();
+        let polynomial = Polynomial::random(rng, &group, share.get_share().clone(), threshold);
+
+        Self::with_roles(
+            &group,
+            Mode::Reshare { previous },
+            Some((share.index(), polynomial)),
+            receiver,
+            threshold,
+            previous_parties,
+            parties,
+        )
+    }
+
+    /// Resharing by a member of the new committee who held no share of the old key.
+    pub fn reshare_receiver(
+        previous: &ThresholdPublicKey<G>,
+        previous_parties: u32,
+        index: u32,
+        threshold: usize,
+        parties: u32,
+    ) -> Result<Self> {
+        Self::with_roles(
+            previous.group(),
+            Mode::Reshare {
+                previous: previous.clone(),
+            },
+            None,
+            Some(index),
+            threshold,
+            previous_parties,
+            parties,
+        )
+    }
+
+    /// Returns the index of the party as a receiver of shares.
+    pub fn index(&self) -> Option<u32> {
+        self.receiver
+    }
+
+    /// Returns the index of the party as a dealer.
+    pub fn dealer_index(&self) -> Option<u32> {
+        self.dealer.as_ref().map(|(index, _)| *index)
+    }
+
+    /// Returns the current phase.
+    pub fn phase(&self) -> DkgPhase {
+        self.phase
+    }
+
+    /// Returns the dealers excluded from the key so far.
+    pub fn disqualified(&self) -> &BTreeSet<u32> {
+        &self.disqualified
+    }
+
+    /// Messages of the first round: the commitments and a share for every other receiver.
+    pub fn start(&self) -> Vec<DkgMessage<G>> {
+        let (dealer, polynomial) = match self.dealer {
+            Some((dealer, ref polynomial)) => (dealer, polynomial),
+            None => return Vec::new(),
+        };
+
+        let mut messages = vec![DkgMessage::Commitments {
+            dealer,
+            commitments: self.commitments[&dealer].clone(),
+        }];
+
+        messages.extend(
+            (1..=self.parties)
+                .filter(|&recipient| Some(recipient) != self.receiver)
+                .map(|recipient| DkgMessage::Share {
+                    dealer,
+                    recipient,
+                    share: polynomial.evaluate(&self.group, recipient),
+                }),
+        );
+
+        messages
+    }
+
+    fn check_dealer(&self, index: u32) -> Result<()> {
+        match index == 0 || index > self.dealers {
+            true => Err(Error::InvalidData),
+            false => Ok(()),
+        }
+    }
+
+    fn check_receiver(&self, index: u32) -> Result<()> {
+        match index == 0 || index > self.parties {
+            true => Err(Error::InvalidData),
+            false => Ok(()),
+        }
+    }
+
+    /// Checks the commitments of `dealer` against the mode of the session.
+    fn valid_commitments(&self, dealer: u32, commitments: &[G::Element]) -> bool {
+        if commitments.len() != self.threshold
+            || !commitments.iter().all(|c| self.group.is_element(c))
+        {
+            return false;
+        }
+
+        match self.mode {
+            Mode::Generate => true,
+            Mode::Refresh { .. } => commitments[0] == self.group.identity(),
+            Mode::Reshare { ref previous } => commitments[0] == previous.verification_key(dealer),
+        }
+    }
+
+    /// Processes a message from another party, returning the messages to send in response.
+    pub fn handle(&mut self, message: DkgMessage<G>) -> Result<Vec<DkgMessage<G>>> {
+        match message {
+            DkgMessage::Commitments {
+                dealer,
+                commitments,
+            } => {
+                self.check_dealer(dealer)?;
+                if self.phase != DkgPhase::Sharing {
+                    return Err(Error::InvalidState);
+                }
+
+                let valid = self.valid_commitments(dealer, &commitments);
+                match self.commitments.get(&dealer) {
+                    Some(previous) if *previous != commitments => {
+                        self.disqualified.insert(dealer);
+                    }
+                    _ if !valid => {
+                        self.disqualified.insert(dealer);
+                    }
+                    _ => {
+                        self.commitments.insert(dealer, commitments);
+                    }
+                }
+
+                Ok(Vec::new())
+            }
+            DkgMessage::Share {
+                dealer,
+                recipient,
+                share,
+            } => {
+                self.check_dealer(dealer)?;
+                if Some(recipient) != self.receiver {
+                    return Err(Error::InvalidData);
+                }
+                if self.phase != DkgPhase::Sharing {
+                    return Err(Error::InvalidState);
+                }
+
+                self.shares.insert(dealer, share);
+
+                Ok(Vec::new())
+            }
+            DkgMessage::Complaint { dealer, accuser } => {
+                self.check_dealer(dealer)?;
+                self.check_receiver(accuser)?;
+                if self.phase == DkgPhase::Finished {
+                    return Err(Error::InvalidState);
+                }
+
+                self.complaints.insert((dealer, accuser));
+                let polynomial = match self.dealer {
+                    Some((index, ref polynomial)) if index == dealer => polynomial,
+                    _ => return Ok(Vec::new()),
+                };
+
+                let share = polynomial.evaluate(&self.group, accuser);
+                self.justified.insert((dealer, accuser));
+                Ok(vec![DkgMessage::Justification {
+                    dealer,
+                    recipient: accuser,
+                    share,
+                }])
+            }
+            DkgMessage::Justification {
+                dealer,
+                recipient,
+                share,
+            } => {
+                self.check_dealer(dealer)?;
+                self.check_receiver(recipient)?;
+                match self.phase {
+                    // A faster party may already complain and get an answer while this
+                    // one is still waiting for shares: keep it until the phase closes.
+                    DkgPhase::Sharing => {
+                        self.pending.insert((dealer, recipient), share);
+                    }
+                    DkgPhase::Complaining => self.justify(dealer, recipient, share),
+                    DkgPhase::Finished => return Err(Error::InvalidState),
+                }
+
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    /// Checks the share of `recipient` revealed by `dealer`.
+    fn justify(&mut self, dealer: u32, recipient: u32, share: G::Scalar) {
+        let valid = self
+            .commitments
+            .get(&dealer)
+            .map(|commitments| verify_share(&self.group, commitments, recipient, &share))
+            .unwrap_or(false);
+
+        match valid {
+            true => {
+                self.justified.insert((dealer, recipient));
+                if Some(recipient) == self.receiver {
+                    self.shares.insert(dealer, share);
+                }
+            }
+            false => {
+                self.disqualified.insert(dealer);
+            }
+        }
+    }
+
+    /// Closes the current phase.
+    ///
+    /// Leaving [`DkgPhase::Sharing`] disqualifies dealers that did not broadcast
+    /// commitments and returns complaints against dealers whose share is missing or
+    /// invalid; justifications received early are checked at this point. Leaving
+    /// [`DkgPhase::Complaining`] disqualifies dealers that did not justify every
+    /// complaint raised against them.
+    pub fn advance(&mut self) -> Result<Vec<DkgMessage<G>>> {
+        match self.phase {
+            DkgPhase::Sharing => {
+                let own = self.dealer_index();
+                let mut messages = Vec::new();
+                for dealer in (1..=self.dealers).filter(|&dealer| Some(dealer) != own) {
+                    let commitments = match self.commitments.get(&dealer) {
+                        Some(commitments) => commitments,
+                        None => {
+                            self.disqualified.insert(dealer);
+                            continue;
+                        }
+                    };
+
+                    let receiver = match self.receiver {
+                        Some(receiver) => receiver,
+                        None => continue,
+                    };
+                    let valid = self
+                        .shares
+                        .get(&dealer)
+                        .map(|share| verify_share(&self.group, commitments, receiver, share))
+                        .unwrap_or(false);
+                    if !valid {
+                        self.complaints.insert((dealer, receiver));
+                        messages.push(DkgMessage::Complaint {
+                            dealer,
+                            accuser: receiver,
+                        });
+                    }
+                }
+
+                self.phase = DkgPhase::Complaining;
+                for ((dealer, recipient), share) in std::mem::take(&mut self.pending) {
+                    self.justify(dealer, recipient, share);
+                }
+
+                Ok(messages)
+            }
+            DkgPhase::Complaining => {
+                for complaint in self.complaints.iter() {
+                    if !self.justified.contains(complaint) {
+                        self.disqualified.insert(complaint.0);
+                    }
+                }
+
+                self.phase = DkgPhase::Finished;
+                Ok(Vec::new())
+            }
+            DkgPhase::Finished => Err(Error::InvalidState),
+        }
+    }
+
+    /// Qualified dealers whose secrets make up the joint key.
+    pub fn qualified(&self) -> Vec<u32> {
+        self.commitments
+            .keys()
+            .filter(|dealer| !self.disqualified.contains(dealer))
+            .copied()
+            .collect()
+    }
+
+    /// Computes the share of the joint key once the protocol is finished.
+    pub fn finalize(&self) -> Result<ThresholdKeyShare<G>> {
+        let receiver = self.receiver.ok_or(Error::InvalidState)?;
+        if self.phase != DkgPhase::Finished {
+            return Err(Error::InvalidState);
+        }
+
+        let qualified = self.qualified();
+        let required = match self.mode {
+            Mode::Generate => self.threshold,
+            Mode::Refresh { .. } => 1,
+            Mode::Reshare { ref previous } => previous.threshold(),
+        };
+        if qualified.len() < required {
+            return Err(Error::NotEnoughShares);
+        }
+
+        let (mut share, mut commitments) = match self.mode {
+            Mode::Refresh {
+                ref previous,
+                ref share,
+            } => (share.clone(), previous.commitments().to_vec()),
+            _ => (
+                self.group.scalar_from_u64(0),
+                vec![self.group.identity(); self.threshold],
+            ),
+        };
+
+        for dealer in qualified.iter() {
+            let weight = match self.mode {
+                Mode::Reshare { .. } => lagrange_coefficient(&self.group, *dealer, &qualified)?,
+                _ => self.group.scalar_from_u64(1),
+            };
+
+            let dealer_share = self.shares.get(dealer).ok_or(Error::InvalidShare)?;
+            share = self
+                .group
+                .scalar_add(&share, &self.group.scalar_mul(&weight, dealer_share));
+
+            for (joint, c) in commitments.iter_mut().zip(self.commitments[dealer].iter()) {
+                *joint = self.group.mul(joint, &self.group.exp(c, &weight));
+            }
+        }
+
+        let public = ThresholdPublicKey::new(self.group.clone(), commitments)?;
+        if let Mode::Refresh { ref previous, .. } | Mode::Reshare { ref previous } = self.mode {
+            if public.get_y() != previous.get_y() {
+                return Err(Error::InvalidShare);
+            }
+        }
+
+        ThresholdKeyShare::new(receiver, share, public)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{prelude::StdRng, SeedableRng};
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::internal::group_encrypt;
+    use crate::ElgamalGroup;
+
+    /// Delivers messages until nobody has anything left to say in the current phase.
+    /// Senders are identified by their position in `participants`.
+    fn deliver<G: Group>(
+        participants: &mut [DkgParticipant<G>],
+        mut messages: Vec<(usize, DkgMessage<G>)>,
+    ) {
+        while let Some((sender, message)) = messages.pop() {
+            for (position, participant) in participants.iter_mut().enumerate() {
+                if position == sender {
+                    continue;
+                }
+                if let DkgMessage::Share { recipient, .. } = message {
+                    if participant.index() != Some(recipient) {
+                        continue;
+                    }
+                }
+
+                let responses = participant.handle(message.clone()).unwrap();
+                messages.extend(responses.into_iter().map(|response| (position, response)));
+            }
+        }
+    }
+
+    fn run<G: Group>(participants: &mut [DkgParticipant<G>], tamper: impl Fn(&mut DkgMessage<G>)) {
+        let mut messages = Vec::new();
+        for (position, participant) in participants.iter().enumerate() {
+            for mut message in participant.start() {
+                tamper(&mut message);
+                messages.push((position, message));
+            }
+        }
+        deliver(participants, messages);
+
+        for _ in 0..2 {
+            let mut messages = Vec::new();
+            for (position, participant) in participants.iter_mut().enumerate() {
+                let round = participant.advance().unwrap();
+                messages.extend(round.into_iter().map(|message| (position, message)));
+            }
+            deliver(participants, messages);
+        }
+    }
+
+    fn participants(
+        rng: &mut StdRng,
+        group: &ElgamalGroup,
+        threshold: usize,
+        parties: u32,
+    ) -> Vec<DkgParticipant<ElgamalGroup>> {
+        (1..=parties)
+            .map(|index| DkgParticipant::new(rng, group, index, threshold, parties).unwrap())
+            .collect()
+    }
+
+    fn generate(
+        rng: &mut StdRng,
+        group: &ElgamalGroup,
+        threshold: usize,
+        parties: u32,
+    ) -> Vec<ThresholdKeyShare<ElgamalGroup>> {
+        let mut participants = participants(rng, group, threshold, parties);
+        run(&mut participants, |_| {});
+
+        participants
+            .iter()
+            .map(|participant| participant.finalize().unwrap())
+            .collect()
+    }
+
+    /// Threshold decryption of a fresh ciphertext with the given shares.
+    fn decrypts(rng: &mut StdRng, shares: &[ThresholdKeyShare<ElgamalGroup>]) -> bool {
+        let mut digest = Sha256::new();
+        let public = shares[0].public();
+        let group = public.group();
+
+        let m = group.exp_generator(&group.random_scalar(rng));
+        let (a, b) = group_encrypt(rng, group, public.get_y(), &m);
+        let decryption_shares: Vec<_> = shares
+            .iter()
+            .map(|share| share.decryption_share(rng, &mut digest, &a).unwrap())
+            .collect();
+
+        public.combine(&mut digest, &a, &b, &decryption_shares).ok() == Some(m)
+    }
+
+    #[test]
+    fn dkg_honest() {
+        let mut rng = StdRng::from_entropy();
+        let group = ElgamalGroup::generate(&mut rng, 128, 64);
+
+        let shares = generate(&mut rng, &group, 3, 5);
+        let public = shares[0].public().clone();
+        assert!(shares.iter().all(|share| *share.public() == public));
+
+        for share in shares.iter() {
+            assert_eq!(
+                group.exp_generator(share.get_share()),
+                public.verification_key(share.index())
+            );
+        }
+        assert!(decrypts(&mut rng, &shares[2..]));
+    }
+
+    #[test]
+    fn dkg_cheating_dealer() {
+        let mut rng = StdRng::from_entropy();
+        let group = ElgamalGroup::generate(&mut rng, 128, 64);
+        let mut participants = participants(&mut rng, &group, 2, 4);
+
+        // Dealer 1 sends a corrupted share to party 2 but justifies with the right one.
+        run(&mut participants, |message| {
+            if let DkgMessage::Share {
+                dealer: 1,
+                recipient: 2,
+                share,
+            } = message
+            {
+                *share = group.scalar_add(share, &group.scalar_from_u64(1));
+            }
+        });
+
+        for participant in participants.iter() {
+            assert!(participant.disqualified().is_empty());
+        }
+        let public = participants[0].finalize().unwrap().public().clone();
+        assert_eq!(*participants[1].finalize().unwrap().public(), public);
+
+        // Dealer 4 never sends its share to party 3.
+        let mut participants = self::participants(&mut rng, &group, 2, 4);
+        let mut messages = Vec::new();
+        for (position, participant) in participants.iter().enumerate() {
+            for message in participant.start() {
+                if !matches!(
+                    message,
+                    DkgMessage::Share {
+                        dealer: 4,
+                        recipient: 3,
+                        ..
+                    }
+                ) {
+                    messages.push((position, message));
+                }
+            }
+        }
+        deliver(&mut participants, messages);
+        let complaints: Vec<_> = participants
+            .iter_mut()
+            .flat_map(|participant| participant.advance().unwrap())
+            .collect();
+        assert_eq!(
+            complaints,
+            vec![DkgMessage::Complaint {
+                dealer: 4,
+                accuser: 3
+            }]
+        );
+
+        // Party 4 goes silent and does not justify.
+        deliver(
+            &mut participants[..3],
+            complaints.into_iter().map(|message| (2, message)).collect(),
+        );
+        for participant in participants[..3].iter_mut() {
+            participant.advance().unwrap();
+            assert_eq!(participant.qualified(), vec![1, 2, 3]);
+        }
+
+        let shares: Vec<_> = participants[..3]
+            .iter()
+            .map(|participant| participant.finalize().unwrap())
+            .collect();
+        assert_eq!(shares[0].public(), shares[2].public());
+        assert!(decrypts(&mut rng, &shares[1..]));
+    }
+
+    #[test]
+    fn dkg_early_justification() {
+        let mut rng = StdRng::from_entropy();
+        let group = ElgamalGroup::generate(&mut rng, 128, 64);
+        let mut participants = participants(&mut rng, &group, 2, 3);
+
+        // Dealer 1 sends a corrupted share to party 2.
+        let mut messages = Vec::new();
+        for (position, participant) in participants.iter().enumerate() {
+            for mut message in participant.start() {
+                if let DkgMessage::Share {
+                    dealer: 1,
+                    recipient: 2,
+                    ref mut share,
+                } = message
+                {
+                    *share = group.scalar_add(share, &group.scalar_from_u64(1));
+                }
+                messages.push((position, message));
+            }
+        }
+        deliver(&mut participants, messages);
+
+        // Parties 1 and 2 close the sharing phase; the complaint and its justification
+        // reach party 3 before it does.
+        let complaints = participants[1].advance().unwrap();
+        participants[0].advance().unwrap();
+        deliver(
+            &mut participants,
+            complaints.into_iter().map(|message| (1, message)).collect(),
+        );
+        assert_eq!(participants[2].phase(), DkgPhase::Sharing);
+
+        for participant in participants.iter_mut() {
+            participant.advance().unwrap();
+        }
+        assert_eq!(participants[2].phase(), DkgPhase::Complaining);
+        participants[2].advance().unwrap();
+        assert!(participants
+            .iter()
+            .all(|participant| participant.disqualified().is_empty()));
+
+        let shares: Vec<_> = participants
+            .iter()
+            .map(|participant| participant.finalize().unwrap())
+            .collect();
+        assert!(shares
+            .iter()
+            .all(|share| share.public() == shares[0].public()));
+        assert!(decrypts(&mut rng, &shares[1..]));
+    }
+
+    #[test]
+    fn refresh() {
+        let mut rng = StdRng::from_entropy();
+        let group = ElgamalGroup::generate(&mut rng, 128, 64);
+        let shares = generate(&mut rng, &group, 3, 5);
+
+        let mut participants: Vec<_> = shares
+            .iter()
+            .map(|share| DkgParticipant::refresh(&mut rng, share, 5).unwrap())
+            .collect();
+        run(&mut participants, |_| {});
+
+        let refreshed: Vec<_> = participants
+            .iter()
+            .map(|participant| participant.finalize().unwrap())
+            .collect();
+        assert_eq!(refreshed[0].public().get_y(), shares[0].public().get_y());
+        assert_ne!(refreshed[0].get_share(), shares[0].get_share());
+        assert!(decrypts(&mut rng, &refreshed[..3]));
+
+        // Old shares no longer match the refreshed commitments.
+        assert!(matches!(
+            ThresholdKeyShare::new(
+                3,
+                shares[2].get_share().clone(),
+                refreshed[0].public().clone()
+            ),
+            Err(Error::InvalidShare)
+        ));
+    }
+
+    #[test]
+    fn reshare() {
+        let mut rng = StdRng::from_entropy();
+        let group = ElgamalGroup::generate(&mut rng, 128, 64);
+        let shares = generate(&mut rng, &group, 2, 3);
+        let previous = shares[0].public().clone();
+
+        // Old members 1 and 2 deal, old member 1 also joins the new committee as member 4,
+        // old member 3 is gone. New members 1 to 3 held no share of the old key.
+        let mut participants = vec![
+            DkgParticipant::reshare(&mut rng, &shares[0], 3, Some(4), 3, 4).unwrap(),
+            DkgParticipant::reshare(&mut rng, &shares[1], 3, None, 3, 4).unwrap(),
+        ];
+        for index in 1..=3 {
+            participants.push(DkgParticipant::reshare_receiver(&previous, 3, index, 3, 4).unwrap());
+        }
+        run(&mut participants, |_| {});
+
+        assert!(participants[1].finalize().is_err());
+        let mut reshared: Vec<_> = participants
+            .iter()
+            .filter(|participant| participant.index().is_some())
+            .map(|participant| participant.finalize().unwrap())
+            .collect();
+        reshared.sort_by_key(|share| share.index());
+
+        let public = reshared[0].public();
+        assert_eq!(public.get_y(), previous.get_y());
+        assert_eq!(public.threshold(), 3);
+        assert!(reshared.iter().all(|share| share.public() == public));
+        assert!(decrypts(&mut rng, &reshared[1..]));
+        assert!(!decrypts(&mut rng, &reshared[2..]));
+    }
+}
@@ -0,0 +1,404 @@
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use digest::DynDigest;
+use num_bigint::BigUint;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::algorithms::group_key_generation;
+use crate::error::{Error, Result};
+use crate::group::{expand_hash, Group};
+use crate::internal::{group_decrypt, group_encrypt, group_reencrypt, group_sign, group_verify};
+
+/// Size in bytes of an encoded point or scalar.
+pub const EC_ELEMENT_SIZE: usize = 32;
+
+/// Ristretto255 prime-order group.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Ristretto255;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EcElgamalPublicKey {
+    /// y = x * G
+    pub(crate) y: RistrettoPoint,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EcElgamalPrivateKey {
+    /// Private scalar
+    pub(crate) x: Scalar,
+    /// Public Key
+    public: EcElgamalPublicKey,
+}
+
+impl EcElgamalPublicKey {
+    pub fn new(y: RistrettoPoint) -> Self {
+        Self { y }
+    }
+
+    /// Returns the public point of the key.
+    pub fn get_y(&self) -> &RistrettoPoint {
+        &self.y
+    }
+}
+
+impl EcElgamalPrivateKey {
+    pub fn new(x: Scalar) -> Self {
+        let public = EcElgamalPublicKey::new(RistrettoPoint::mul_base(&x));
+        Self { x, public }
+    }
+
+    /// Returns the private scalar of the key.
+    pub fn get_x(&self) -> &Scalar {
+        &self.x
+    }
+
+    /// Returns the public key.
+    pub fn public(&self) -> &EcElgamalPublicKey {
+        &self.public
+    }
+}
+
+/// Order of the Ristretto group.
+pub fn ec_group_order() -> BigUint {
+    BigUint::from_bytes_le((-Scalar::ONE).as_bytes()) + 1u32
+}
+
+/// Reduces a big-endian integer modulo the order of the Ristretto group.
+pub(crate) fn scalar_from_bytes_be(bytes: &[u8]) -> Scalar {
+    let mut wide = [0u8; 64];
+    if bytes.len() <= wide.len() {
+        for (dst, src) in wide.iter_mut().zip(bytes.iter().rev()) {
+            *dst = *src;
+        }
+        return Scalar::from_bytes_mod_order_wide(&wide);
+    }
+
+    let reduced = (BigUint::from_bytes_be(bytes) % ec_group_order()).to_bytes_le();
+    wide[..reduced.len()].copy_from_slice(&reduced);
+    Scalar::from_bytes_mod_order_wide(&wide)
+}
+
+pub(crate) fn decode_point(bytes: &[u8]) -> Result<RistrettoPoint> {
+    CompressedRistretto::from_slice(bytes)
+        .map_err(|_| Error::InvalidData)?
+        .decompress()
+        .ok_or(Error::InvalidData)
+}
+
+pub(crate) fn decode_scalar(bytes: &[u8]) -> Result<Scalar> {
+    let bytes: [u8; EC_ELEMENT_SIZE] = bytes.try_into().map_err(|_| Error::InvalidData)?;
+    Option::from(Scalar::from_canonical_bytes(bytes)).ok_or(Error::InvalidData)
+}
+
+impl Group for Ristretto255 {
+    type Element = RistrettoPoint;
+    type Scalar = Scalar;
+
+    fn generator(&self) -> RistrettoPoint {
+        RISTRETTO_BASEPOINT_POINT
+    }
+
+    fn identity(&self) -> RistrettoPoint {
+        RistrettoPoint::identity()
+    }
+
+    fn order(&self) -> BigUint {
+        ec_group_order()
+    }
+
+    fn mul(&self, a: &RistrettoPoint, b: &RistrettoPoint) -> RistrettoPoint {
+        a + b
+    }
+
+    fn invert(&self, a: &RistrettoPoint) -> Option<RistrettoPoint> {
+        Some(-a)
+    }
+
+    fn exp(&self, base: &RistrettoPoint, e: &Scalar) -> RistrettoPoint {
+        base * e
+    }
+
+    fn exp_generator(&self, e: &Scalar) -> RistrettoPoint {
+        RistrettoPoint::mul_base(e)
+    }
+
+    fn is_element(&self, _a: &RistrettoPoint) -> bool {
+        true
+    }
+
+    fn element_size(&self) -> usize {
+        EC_ELEMENT_SIZE
+    }
+
+    fn element_to_bytes(&self, a: &RistrettoPoint) -> Vec<u8> {
+        a.compress().to_bytes().to_vec()
+    }
+
+    fn element_from_bytes(&self, bytes: &[u8]) -> Result<RistrettoPoint> {
+        decode_point(bytes)
+    }
+
+    fn element_to_scalar(&self, a: &RistrettoPoint) -> Scalar {
+        Scalar::from_bytes_mod_order(a.compress().to_bytes())
+    }
+
+    fn hash_to_element(&self, digest: &mut dyn DynDigest, input: &[u8]) -> RistrettoPoint {
+        let mut bytes = [0u8; 64];
+        bytes.copy_from_slice(&expand_hash(digest, input, 64));
+
+        RistrettoPoint::from_uniform_bytes(&bytes)
+    }
+
+    fn random_scalar<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Scalar {
+        loop {
+            let s = Scalar::random(rng);
+            if s != Scalar::ZERO {
+                break s;
+            }
+        }
+    }
+
+    fn scalar_from_biguint(&self, n: &BigUint) -> Scalar {
+        scalar_from_bytes_be(&n.to_bytes_be())
+    }
+
+    fn scalar_to_biguint(&self, s: &Scalar) -> BigUint {
+        BigUint::from_bytes_le(s.as_bytes())
+    }
+
+    fn scalar_from_u64(&self, n: u64) -> Scalar {
+        Scalar::from(n)
+    }
+
+    fn scalar_add(&self, a: &Scalar, b: &Scalar) -> Scalar {
+        a + b
+    }
+
+    fn scalar_sub(&self, a: &Scalar, b: &Scalar) -> Scalar {
+        a - b
+    }
+
+    fn scalar_mul(&self, a: &Scalar, b: &Scalar) -> Scalar {
+        a * b
+    }
+
+    fn scalar_neg(&self, a: &Scalar) -> Scalar {
+        -a
+    }
+
+    fn scalar_invert(&self, a: &Scalar) -> Option<Scalar> {
+        match *a == Scalar::ZERO {
+            true => None,
+            false => Some(a.invert()),
+        }
+    }
+
+    fn scalar_size(&self) -> usize {
+        EC_ELEMENT_SIZE
+    }
+
+    fn scalar_to_bytes(&self, s: &Scalar) -> Vec<u8> {
+        s.to_bytes().to_vec()
+    }
+
+    fn scalar_from_bytes(&self, bytes: &[u8]) -> Result<Scalar> {
+        decode_scalar(bytes)
+    }
+}
+
+#[inline]
+pub fn ec_encrypt<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    key: &EcElgamalPublicKey,
+    m: &RistrettoPoint,
+) -> (RistrettoPoint, RistrettoPoint) {
+    group_encrypt(rng, &Ristretto255, &key.y, m)
+}
+
+#[inline]
+pub fn ec_reencrypt<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    key: &EcElgamalPublicKey,
+    a: &RistrettoPoint,
+    b: &RistrettoPoint,
+) -> (RistrettoPoint, RistrettoPoint) {
+    group_reencrypt(rng, &Ristretto255, &key.y, a, b)
+}
+
+#[inline]
+pub fn ec_decrypt(
+    key: &EcElgamalPrivateKey,
+    a: &RistrettoPoint,
+    b: &RistrettoPoint,
+) -> Result<RistrettoPoint> {
+    group_decrypt(&Ristretto255, &key.x, a, b)
+}
+
+#[inline]
+pub fn ec_sign<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    key: &EcElgamalPrivateKey,
+    h: &Scalar,
+) -> Result<(RistrettoPoint, Scalar)> {
+    group_sign(rng, &Ristretto255, &key.x, h)
+}
+
+#[inline]
+pub fn ec_verify(
+    key: &EcElgamalPublicKey,
+    h: &Scalar,
+    r: &RistrettoPoint,
+    s: &Scalar,
+) -> Result<()> {
+    group_verify(&Ristretto255, &key.y, h, r, s)
+}
+
+impl EcElgamalPublicKey {
+    /// Encrypt the given point.
+    pub fn encrypt<R: RngCore + CryptoRng>(&self, rng: &mut R, msg: &RistrettoPoint) -> Vec<u8> {
+        let (a, b) = ec_encrypt(rng, self, msg);
+
+        let mut data = a.compress().to_bytes().to_vec();
+        data.extend_from_slice(b.compress().as_bytes());
+
+        data
+    }
+
+    /// Verify a signed message.
+    /// `hashed` must be the result of hashing the input message.
+    /// If the message is valid `Ok(())` is returned, otherwiese an `Err` indicating failure.
+    pub fn verify(&self, hashed: &[u8], sig: &[u8]) -> Result<()> {
+        if sig.len() != 2 * EC_ELEMENT_SIZE {
+            return Err(Error::InvalidData);
+        }
+
+        let (r, s) = sig.split_at(EC_ELEMENT_SIZE);
+        let r = decode_point(r)?;
+        let s = decode_scalar(s)?;
+
+        ec_verify(self, &scalar_from_bytes_be(hashed), &r, &s)
+    }
+}
+
+impl EcElgamalPrivateKey {
+    /// Decrypt the given ciphertext into a point.
+    pub fn decrypt(&self, ciphertext: &[u8]) -> Result<RistrettoPoint> {
+        if ciphertext.len() != 2 * EC_ELEMENT_SIZE {
+            return Err(Error::InvalidData);
+        }
+
+        let (a, b) = ciphertext.split_at(EC_ELEMENT_SIZE);
+        let a = decode_point(a)?;
+        let b = decode_point(b)?;
+
+        ec_decrypt(self, &a, &b)
+    }
+
+    /// Signe message.
+    /// `hashed` must be the result of hashing the input message.
+    pub fn sign<R: RngCore + CryptoRng>(&self, rng: &mut R, hashed: &[u8]) -> Result<Vec<u8>> {
+        let (r, s) = ec_sign(rng, self, &scalar_from_bytes_be(hashed))?;
+
+        let mut data = r.compress().to_bytes().to_vec();
+        data.extend_from_slice(s.as_bytes());
+
+        Ok(data)
+    }
+}
+
+pub fn ec_public_key_encode(public_key: &EcElgamalPublicKey) -> [u8; EC_ELEMENT_SIZE] {
+    public_key.y.compress().to_bytes()
+}
+
+pub fn ec_private_key_encode(private_key: &EcElgamalPrivateKey) -> [u8; EC_ELEMENT_SIZE] {
+    private_key.x.to_bytes()
+}
+
+pub fn ec_public_key_decode(data: impl AsRef<[u8]>) -> Result<EcElgamalPublicKey> {
+    let y = decode_point(data.as_ref()).map_err(|_| Error::PublicKeyMalformed)?;
+
+    Ok(EcElgamalPublicKey::new(y))
+}
+
+pub fn ec_private_key_decode(data: impl AsRef<[u8]>) -> Result<EcElgamalPrivateKey> {
+    let x = decode_scalar(data.as_ref()).map_err(|_| Error::PrivateKeyMalformed)?;
+    if x == Scalar::ZERO {
+        return Err(Error::PrivateKeyMalformed);
+    }
+
+    Ok(EcElgamalPrivateKey::new(x))
+}
+
+pub fn ec_elgamal_key_generate<R: RngCore + CryptoRng>(
+    rng: &mut R,
+) -> (EcElgamalPublicKey, EcElgamalPrivateKey) {
+    let (y, x) = group_key_generation(rng, &Ristretto255);
+
+    (EcElgamalPublicKey::new(y), EcElgamalPrivateKey::new(x))
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{prelude::StdRng, SeedableRng};
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+
+    #[test]
+    fn encrypt_reencrypt_decrypt() {
+        let mut rng = StdRng::from_entropy();
+        let (pub_key, priv_key) = ec_elgamal_key_generate(&mut rng);
+        let plain_text = RistrettoPoint::random(&mut rng);
+
+        let (a, b) = ec_encrypt(&mut rng, &pub_key, &plain_text);
+        assert_eq!(ec_decrypt(&priv_key, &a, &b).unwrap(), plain_text);
+
+        let (a1, b1) = ec_reencrypt(&mut rng, &pub_key, &a, &b);
+        assert_ne!((a, b), (a1, b1));
+        assert_eq!(ec_decrypt(&priv_key, &a1, &b1).unwrap(), plain_text);
+
+        let ciphertext = pub_key.encrypt(&mut rng, &plain_text);
+        assert_eq!(priv_key.decrypt(&ciphertext).unwrap(), plain_text);
+    }
+
+    #[test]
+    fn sign_verify() {
+        let mut rng = StdRng::from_entropy();
+        let (pub_key, priv_key) = ec_elgamal_key_generate(&mut rng);
+        let hashed = Sha256::digest(b"message");
+
+        let sig = priv_key.sign(&mut rng, &hashed).unwrap();
+        pub_key.verify(&hashed, &sig).unwrap();
+
+        let other = Sha256::digest(b"other message");
+        assert!(matches!(
+            pub_key.verify(&other, &sig),
+            Err(Error::Verification)
+        ));
+    }
+
+    #[test]
+    fn keys_encoding() {
+        let mut rng = StdRng::from_entropy();
+        let (pub_key, priv_key) = ec_elgamal_key_generate(&mut rng);
+
+        let decoded = ec_public_key_decode(ec_public_key_encode(&pub_key)).unwrap();
+        assert_eq!(decoded, pub_key);
+
+        let decoded = ec_private_key_decode(ec_private_key_encode(&priv_key)).unwrap();
+        assert_eq!(decoded, priv_key);
+
+        assert!(ec_public_key_decode([0xffu8; EC_ELEMENT_SIZE]).is_err());
+    }
+
+    #[test]
+    fn scalar_reduction() {
+        let bytes = [0xffu8; 80];
+        let expected = BigUint::from_bytes_be(&bytes) % ec_group_order();
+
+        let scalar = scalar_from_bytes_be(&bytes);
+        assert_eq!(BigUint::from_bytes_le(scalar.as_bytes()), expected);
+    }
+}
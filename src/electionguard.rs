@@ -0,0 +1,553 @@
+use hmac::{Hmac, Mac};
+use num_bigint::BigUint;
+use num_traits::Num;
+use rand_core::{CryptoRng, RngCore};
+use sha2::Sha256;
+
+use crate::error::{Error, Result};
+use crate::group::Group;
+use crate::keys::{ElgamalGroup, ElgamalGroupElements, ElgamalPrivateKey, ElgamalPublicKey};
+use crate::tally::solve_dlog;
+
+// Standard parameters of ElectionGuard 2.0: `q = 2^256 - 189`, and `p` the
+// smallest prime `2^4096 - 2^3840 + 2^256 (floor(2^3584 ln 2) + d) - 1` with
+// `q | p - 1`, and `g = 2^((p - 1) / q) mod p`.
+const P_HEX: &str = concat!(
+    "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF",
+    "B17217F7D1CF79ABC9E3B39803F2F6AF40F343267298B62D8A0D175B8BAAFA2B",
+    "E7B876206DEBAC98559552FB4AFA1B10ED2EAE35C138214427573B291169B825",
+    "3E96CA16224AE8C51ACBDA11317C387EB9EA9BC3B136603B256FA0EC7657F74B",
+    "72CE87B19D6548CAF5DFA6BD38303248655FA1872F20E3A2DA2D97C50F3FD5C6",
+    "07F4CA11FB5BFB90610D30F88FE551A2EE569D6DFC1EFA157D2E23DE1400B396",
+    "17460775DB8990E5C943E732B479CD33CCCC4E659393514C4C1A1E0BD1D6095D",
+    "25669B333564A3376A9C7F8A5E148E82074DB6015CFE7AA30C480A5417350D2C",
+    "955D5179B1E17B9DAE313CDB6C606CB1078F735D1B2DB31B5F50B5185064C18B",
+    "4D162DB3B365853D7598A1951AE273EE5570B6C68F96983496D4E6D330AF889B",
+    "44A02554731CDC8EA17293D1228A4EF98D6F5177FBCF0755268A5C1F9538B982",
+    "61AFFD446B1CA3CF5E9222B88C66D3C5422183EDC99421090BBB16FAF3D949F2",
+    "36E02B20CEE886B905C128D53D0BD2F9621363196AF503020060E49908391A0C",
+    "57339BA2BEBA7D052AC5B61CC4E9207CEF2F0CE2D7373958D7622658901E7B55",
+    "FB5F2DA4B751005892D356890DEFE9CAD9B9D4B713E06162A2D8FDD0FB23357B",
+    "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF",
+);
+const Q_HEX: &str = "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF43";
+const G_HEX: &str = concat!(
+    "F9553B6E088C3AE7076BFD13D2839EB8204DE6FB47928C8BDA5A3F7DA4BA27BA",
+    "650B8DC08A20D25E293B86A0C9E5C9507EFCD5D01E255A5C05EEEB8417ED52C1",
+    "ADF6FA554D20762D7EE6BFC3407A3727E4E952E33DEAEA51280B179C1C40075B",
+    "54F2FACBDD1C825B282DB381FD785625AF2FD35F7A1504B59681631DB27BE727",
+    "21D97F617F24375E2E24E2600C864D6F55A13495BF6B1B64A39D9A1DB4B39CC4",
+    "290CB99B9B89A63010E4FA90EF5C712C448254470BF9197B3368581469995F47",
+    "13F11E9C7D7B0EFB97FE04F05F5EA784DC11FD5474A644B0D4CDA8CED1B62C7C",
+    "06EC91C969D372C541A4F5CEFD734646F973AC43F736923BCE37CA805954B7BB",
+    "8D0F0B9F783115114A3DE9DCD6FEC9319B64F22D014628ED6B297B2C495D4900",
+    "8DE86CC5C52CD866E3E96BEBB6AB25ADFC86B9063B2C995FEDB3C0713490EF33",
+    "56347A60F63A1DE326AC7718A17F27080CE89F8DC86787F4879970E8612D8012",
+    "F7E1BEC129D36CE4784F635532BEFD6AB3445108A25F743B6670BAE28A48EAB8",
+    "F89EDEA23FE677812D2939C3F96BAFB272E4DD77136249ACBF3ED294CE92CA57",
+    "52A80D3DC0677385292A883BEA76A515CFD0FA20B903728FAF5D164C4C2D9A25",
+    "8F318D8224BB090E8B4D8ED96D42BB89F3F745B9C17E27C89234FBE198E1DBA8",
+    "31D90476CA84FD98D5E2762FF3F8122B019B33292A2C3CA67C39F28F8EB30A4A",
+);
+
+/// Version string absorbed in the parameter base hash.
+const VERSION: &[u8] = b"v2.0.0";
+
+/// Group of the ElectionGuard 2.0 standard parameters.
+pub fn electionguard_group() -> ElgamalGroup {
+    let parse = |s: &str| BigUint::from_str_radix(s, 16).expect("valid constant");
+    ElgamalGroup::new(parse(P_HEX), parse(Q_HEX), parse(G_HEX))
+}
+
+/// Input of the ElectionGuard hash function, encoded big-endian at fixed width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElectionGuardInput<'a> {
+    /// Domain separation byte
+    Byte(u8),
+    /// Index, 4 bytes
+    Index(u32),
+    /// Element of the group, `element_size()` bytes
+    Element(&'a BigUint),
+    /// Integer modulo `q`, `scalar_size()` bytes
+    Scalar(&'a BigUint),
+    /// Raw bytes, e.g. a previous hash
+    Bytes(&'a [u8]),
+}
+
+/// ElectionGuard hash `H(key; input) = HMAC-SHA-256(key, input)`.
+pub fn electionguard_hash(
+    group: &ElgamalGroup,
+    key: &[u8],
+    input: &[ElectionGuardInput],
+) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key size");
+    for item in input {
+        match item {
+            ElectionGuardInput::Byte(b) => mac.update(&[*b]),
+            ElectionGuardInput::Index(i) => mac.update(&i.to_be_bytes()),
+            ElectionGuardInput::Element(e) => mac.update(&group.element_to_bytes(e)),
+            ElectionGuardInput::Scalar(s) => mac.update(&group.scalar_to_bytes(s)),
+            ElectionGuardInput::Bytes(b) => mac.update(b),
+        }
+    }
+
+    mac.finalize().into_bytes().into()
+}
+
+/// Parameter base hash `H_P = H(ver; 0x00, p, q, g)` of a group.
+pub fn electionguard_parameter_hash(group: &ElgamalGroup) -> [u8; 32] {
+    let mut version = [0u8; 32];
+    version[..VERSION.len()].copy_from_slice(VERSION);
+
+    electionguard_hash(
+        group,
+        &version,
+        &[
+            ElectionGuardInput::Byte(0x00),
+            ElectionGuardInput::Element(group.get_p()),
+            ElectionGuardInput::Scalar(group.get_q()),
+            ElectionGuardInput::Element(group.get_g()),
+        ],
+    )
+}
+
+/// Challenge: the hash read as an integer modulo `q`.
+fn challenge(group: &ElgamalGroup, key: &[u8], input: &[ElectionGuardInput]) -> BigUint {
+    group.scalar_from_biguint(&BigUint::from_bytes_be(&electionguard_hash(
+        group, key, input,
+    )))
+}
+
+fn check_elements(group: &ElgamalGroup, elements: &[&BigUint]) -> Result<()> {
+    match elements.iter().all(|e| group.is_element(e)) {
+        true => Ok(()),
+        false => Err(Error::InvalidData),
+    }
+}
+
+fn check_scalars(group: &ElgamalGroup, scalars: &[&BigUint]) -> Result<()> {
+    match scalars.iter().all(|s| *s < group.get_q()) {
+        true => Ok(()),
+        false => Err(Error::InvalidRange),
+    }
+}
+
+/// Exponential encryption `(alpha, beta) = (g^xi, K^(m + xi))` of `m` under the
+/// election key `K`, with nonce `xi`.
+pub fn electionguard_encrypt(key: &ElgamalPublicKey, m: u64, xi: &BigUint) -> (BigUint, BigUint) {
+    let group = key.group();
+    let e = group.scalar_add(&group.scalar_from_u64(m), xi);
+
+    (group.exp_generator(xi), group.exp(key.get_y(), &e))
+}
+
+/// Decrypts `(alpha, beta)` with the election secret `s`: `K^m = beta / alpha^s`,
+/// whose logarithm in base `K` is searched in `[0, bound]`.
+pub fn electionguard_decrypt(
+    key: &ElgamalPrivateKey,
+    alpha: &BigUint,
+    beta: &BigUint,
+    bound: u64,
+) -> Result<u64> {
+    let group = key.group();
+    check_elements(group, &[alpha, beta])?;
+
+    let mask = group
+        .invert(&group.exp(alpha, key.get_x()))
+        .ok_or(Error::InvalidInverse)?;
+    let t = group.mul(beta, &mask);
+    let y = key.to_public_key().get_y().clone();
+    let base = ElgamalGroup::new(group.get_p().clone(), group.get_q().clone(), y);
+
+    solve_dlog(&base, &t, bound)
+}
+
+/// Proof that a ciphertext encrypts an integer in `[0, L]`: one Chaum–Pedersen
+/// branch per value, with challenges `c_j` summing to the hashed challenge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElectionGuardRangeProof {
+    pub challenges: Vec<BigUint>,
+    pub responses: Vec<BigUint>,
+}
+
+/// Commitments `a_j = g^v_j alpha^c_j` and `b_j = K^(v_j - j c_j) beta^c_j` of
+/// branch `j`.
+fn range_commitment(
+    key: &ElgamalPublicKey,
+    (alpha, beta): (&BigUint, &BigUint),
+    j: u64,
+    c: &BigUint,
+    v: &BigUint,
+) -> (BigUint, BigUint) {
+    let group = key.group();
+    let w = group.scalar_sub(v, &group.scalar_mul(&group.scalar_from_u64(j), c));
+
+    (
+        group.mul(&group.exp_generator(v), &group.exp(alpha, c)),
+        group.mul(&group.exp(key.get_y(), &w), &group.exp(beta, c)),
+    )
+}
+
+/// `c = H(H_E; 0x21, K, alpha, beta, a_0, b_0, ..., a_L, b_L)`
+fn range_challenge(
+    key: &ElgamalPublicKey,
+    extended_hash: &[u8],
+    (alpha, beta): (&BigUint, &BigUint),
+    commitments: &[(BigUint, BigUint)],
+) -> BigUint {
+    let mut input = vec![
+        ElectionGuardInput::Byte(0x21),
+        ElectionGuardInput::Element(key.get_y()),
+        ElectionGuardInput::Element(alpha),
+        ElectionGuardInput::Element(beta),
+    ];
+    for (a, b) in commitments {
+        input.push(ElectionGuardInput::Element(a));
+        input.push(ElectionGuardInput::Element(b));
+    }
+
+    challenge(key.group(), extended_hash, &input)
+}
+
+impl ElectionGuardRangeProof {
+    /// Proves that `(alpha, beta)`, encrypted with nonce `xi`, encrypts `m <= limit`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        extended_hash: &[u8],
+        key: &ElgamalPublicKey,
+        alpha: &BigUint,
+        beta: &BigUint,
+        xi: &BigUint,
+        m: u64,
+        limit: u64,
+    ) -> Result<Self> {
+        if m > limit {
+            return Err(Error::InvalidRange);
+        }
+        let group = key.group();
+        let m = usize::try_from(m).map_err(|_| Error::InvalidData)?;
+
+        // Simulate every other branch, commit honestly to the true one.
+        let u = group.random_scalar(rng);
+        let mut challenges = Vec::new();
+        let mut responses = Vec::new();
+        let mut commitments = Vec::new();
+        for j in 0..=limit {
+            if j as usize == m {
+                challenges.push(BigUint::default());
+                responses.push(BigUint::default());
+                commitments.push((group.exp_generator(&u), group.exp(key.get_y(), &u)));
+            } else {
+                let (c, v) = (group.random_scalar(rng), group.random_scalar(rng));
+                commitments.push(range_commitment(key, (alpha, beta), j, &c, &v));
+                challenges.push(c);
+                responses.push(v);
+            }
+        }
+
+        let c = range_challenge(key, extended_hash, (alpha, beta), &commitments);
+        let simulated = challenges
+            .iter()
+            .fold(BigUint::default(), |acc, c| group.scalar_add(&acc, c));
+        challenges[m] = group.scalar_sub(&c, &simulated);
+        responses[m] = group.scalar_sub(&u, &group.scalar_mul(&challenges[m], xi));
+
+        Ok(Self {
+            challenges,
+            responses,
+        })
+    }
+
+    /// Verifies that `(alpha, beta)` encrypts an integer in `[0, limit]`.
+    pub fn verify(
+        &self,
+        extended_hash: &[u8],
+        key: &ElgamalPublicKey,
+        alpha: &BigUint,
+        beta: &BigUint,
+        limit: u64,
+    ) -> Result<()> {
+        let group = key.group();
+        let branches = usize::try_from(limit)
+            .ok()
+            .and_then(|limit| limit.checked_add(1))
+            .ok_or(Error::InvalidData)?;
+        if self.challenges.len() != branches || self.responses.len() != branches {
+            return Err(Error::InvalidData);
+        }
+        check_elements(group, &[key.get_y(), alpha, beta])?;
+        check_scalars(group, &self.challenges.iter().collect::<Vec<_>>())?;
+        check_scalars(group, &self.responses.iter().collect::<Vec<_>>())?;
+
+        let commitments: Vec<_> = (0..=limit)
+            .zip(self.challenges.iter().zip(&self.responses))
+            .map(|(j, (c, v))| range_commitment(key, (alpha, beta), j, c, v))
+            .collect();
+        let c = range_challenge(key, extended_hash, (alpha, beta), &commitments);
+        let sum = self
+            .challenges
+            .iter()
+            .fold(BigUint::default(), |acc, c| group.scalar_add(&acc, c));
+
+        match sum == c {
+            true => Ok(()),
+            false => Err(Error::Verification),
+        }
+    }
+}
+
+/// Proof that `M = A^s` for the secret `s` of a guardian key `K = g^s`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElectionGuardDecryptionProof {
+    pub challenge: BigUint,
+    pub response: BigUint,
+}
+
+/// `c = H(H_E; 0x30, K, A, B, a, b, M)`
+fn decryption_challenge(
+    key: &ElgamalPublicKey,
+    extended_hash: &[u8],
+    (a, b): (&BigUint, &BigUint),
+    (ca, cb): (&BigUint, &BigUint),
+    m: &BigUint,
+) -> BigUint {
+    challenge(
+        key.group(),
+        extended_hash,
+        &[
+            ElectionGuardInput::Byte(0x30),
+            ElectionGuardInput::Element(key.get_y()),
+            ElectionGuardInput::Element(a),
+            ElectionGuardInput::Element(b),
+            ElectionGuardInput::Element(ca),
+            ElectionGuardInput::Element(cb),
+            ElectionGuardInput::Element(m),
+        ],
+    )
+}
+
+impl ElectionGuardDecryptionProof {
+    /// Partial decryption `M = A^s` of `(A, B)` with its proof.
+    pub fn prove<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        extended_hash: &[u8],
+        key: &ElgamalPrivateKey,
+        a: &BigUint,
+        b: &BigUint,
+    ) -> Result<(BigUint, Self)> {
+        let group = key.group();
+        check_elements(group, &[a, b])?;
+
+        let public = key.to_public_key();
+        let m = group.exp(a, key.get_x());
+        let u = group.random_scalar(rng);
+        let commitment = (group.exp_generator(&u), group.exp(a, &u));
+        let c = decryption_challenge(
+            &public,
+            extended_hash,
+            (a, b),
+            (&commitment.0, &commitment.1),
+            &m,
+        );
+        let v = group.scalar_sub(&u, &group.scalar_mul(&c, key.get_x()));
+
+        Ok((
+            m,
+            Self {
+                challenge: c,
+                response: v,
+            },
+        ))
+    }
+
+    /// Verifies that `m` is the partial decryption of `(A, B)` under `key`.
+    pub fn verify(
+        &self,
+        extended_hash: &[u8],
+        key: &ElgamalPublicKey,
+        a: &BigUint,
+        b: &BigUint,
+        m: &BigUint,
+    ) -> Result<()> {
+        let group = key.group();
+        check_elements(group, &[key.get_y(), a, b, m])?;
+        check_scalars(group, &[&self.challenge, &self.response])?;
+
+        // a = g^v K^c, b = A^v M^c
+        let (c, v) = (&self.challenge, &self.response);
+        let ca = group.mul(&group.exp_generator(v), &group.exp(key.get_y(), c));
+        let cb = group.mul(&group.exp(a, v), &group.exp(m, c));
+
+        match *c == decryption_challenge(key, extended_hash, (a, b), (&ca, &cb), m) {
+            true => Ok(()),
+            false => Err(Error::Verification),
+        }
+    }
+}
+
+/// Schnorr proof of knowledge of the coefficient `a_ij` behind the commitment
+/// `K_ij = g^a_ij` published by guardian `i`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElectionGuardCoefficientProof {
+    pub challenge: BigUint,
+    pub response: BigUint,
+}
+
+/// `c = H(H_P; 0x10, i, j, K_ij, h)`
+fn coefficient_challenge(
+    commitment: &ElgamalPublicKey,
+    parameter_hash: &[u8],
+    (i, j): (u32, u32),
+    h: &BigUint,
+) -> BigUint {
+    challenge(
+        commitment.group(),
+        parameter_hash,
+        &[
+            ElectionGuardInput::Byte(0x10),
+            ElectionGuardInput::Index(i),
+            ElectionGuardInput::Index(j),
+            ElectionGuardInput::Element(commitment.get_y()),
+            ElectionGuardInput::Element(h),
+        ],
+    )
+}
+
+impl ElectionGuardCoefficientProof {
+    /// Proves knowledge of coefficient `j` of guardian `i`.
+    pub fn prove<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        parameter_hash: &[u8],
+        i: u32,
+        j: u32,
+        coefficient: &ElgamalPrivateKey,
+    ) -> Self {
+        let group = coefficient.group();
+        let u = group.random_scalar(rng);
+        let h = group.exp_generator(&u);
+        let c = coefficient_challenge(&coefficient.to_public_key(), parameter_hash, (i, j), &h);
+        let v = group.scalar_sub(&u, &group.scalar_mul(&c, coefficient.get_x()));
+
+        Self {
+            challenge: c,
+            response: v,
+        }
+    }
+
+    /// Verifies the proof for the commitment `K_ij` of guardian `i`.
+    pub fn verify(
+        &self,
+        parameter_hash: &[u8],
+        i: u32,
+        j: u32,
+        commitment: &ElgamalPublicKey,
+    ) -> Result<()> {
+        let group = commitment.group();
+        check_elements(group, &[commitment.get_y()])?;
+        check_scalars(group, &[&self.challenge, &self.response])?;
+
+        // h = g^v K_ij^c
+        let h = group.mul(
+            &group.exp_generator(&self.response),
+            &group.exp(commitment.get_y(), &self.challenge),
+        );
+
+        match self.challenge == coefficient_challenge(commitment, parameter_hash, (i, j), &h) {
+            true => Ok(()),
+            false => Err(Error::Verification),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use num_bigint::prime::probably_prime;
+    use num_traits::{One, Zero};
+    use rand::{prelude::StdRng, SeedableRng};
+
+    use super::*;
+
+    /// `floor(2^bits ln 2)`, from `ln 2 = sum 1 / (k 2^k)` with 64 guard bits.
+    fn ln2(bits: usize) -> BigUint {
+        let precision = bits + 64;
+        let sum = (1..=precision).fold(BigUint::zero(), |acc, k| {
+            acc + (BigUint::one() << (precision - k)) / k
+        });
+
+        sum >> 64
+    }
+
+    #[test]
+    fn electionguard_constants() {
+        let group = electionguard_group();
+        let (p, q, g) = (group.get_p(), group.get_q(), group.get_g());
+        let one = BigUint::one();
+
+        assert_eq!(*q, (&one << 256) - 189u32);
+        assert!(probably_prime(q, 20));
+
+        // p = 2^4096 - 2^3840 + 2^256 (floor(2^3584 ln 2) + d) - 1
+        assert_eq!(p.bits(), 4096);
+        assert!(probably_prime(p, 20));
+        assert!(((p - 1u32) % q).is_zero());
+        let top = (&one << 4096) - (&one << 3840);
+        let middle = (p + 1u32 - top) >> 256;
+        assert!(((p + 1u32) % (&one << 256)).is_zero());
+        let d = middle - ln2(3584);
+        assert_eq!(d.bits(), 269);
+
+        assert_eq!(*g, BigUint::from(2u32).modpow(&((p - 1u32) / q), p));
+        assert!(!g.is_one() && group.is_element(g));
+        assert_eq!(group.element_size(), 512);
+        assert_eq!(group.scalar_size(), 32);
+
+        let h_p = "5FE948CC9223B2B551B5364406F316264FC2F2BEB61C0D74B73D8B6763D4CCA9";
+        assert_eq!(
+            BigUint::from_bytes_be(&electionguard_parameter_hash(&group)),
+            BigUint::from_str_radix(h_p, 16).unwrap()
+        );
+    }
+
+    #[test]
+    fn electionguard_proofs() {
+        let mut rng = StdRng::from_entropy();
+        let group = electionguard_group();
+        let h_p = electionguard_parameter_hash(&group);
+        let h_e = electionguard_hash(&group, &h_p, &[ElectionGuardInput::Byte(0x12)]);
+
+        let s = group.random_scalar(&mut rng);
+        let secret = ElgamalPrivateKey::new(group.clone(), s, None);
+        let key = secret.to_public_key();
+
+        // Guardian key commitment
+        let proof = ElectionGuardCoefficientProof::prove(&mut rng, &h_p, 1, 0, &secret);
+        proof.verify(&h_p, 1, 0, &key).unwrap();
+        assert!(proof.verify(&h_p, 2, 0, &key).is_err());
+
+        // Selection in [0, 1]
+        let xi = group.random_scalar(&mut rng);
+        let (alpha, beta) = electionguard_encrypt(&key, 1, &xi);
+        let proof =
+            ElectionGuardRangeProof::prove(&mut rng, &h_e, &key, &alpha, &beta, &xi, 1, 1).unwrap();
+        proof.verify(&h_e, &key, &alpha, &beta, 1).unwrap();
+        assert!(proof.verify(&h_p, &key, &alpha, &beta, 1).is_err());
+        let (alpha2, beta2) = electionguard_encrypt(&key, 2, &xi);
+        assert!(proof.verify(&h_e, &key, &alpha2, &beta2, 1).is_err());
+        assert!(matches!(
+            proof.verify(&h_e, &key, &alpha, &beta, u64::MAX),
+            Err(Error::InvalidData)
+        ));
+        assert_eq!(
+            electionguard_decrypt(&secret, &alpha, &beta, 10).unwrap(),
+            1
+        );
+
+        // Decryption share
+        let (m, proof) =
+            ElectionGuardDecryptionProof::prove(&mut rng, &h_e, &secret, &alpha, &beta).unwrap();
+        proof.verify(&h_e, &key, &alpha, &beta, &m).unwrap();
+        let wrong = group.mul(&m, group.get_g());
+        assert!(proof.verify(&h_e, &key, &alpha, &beta, &wrong).is_err());
+    }
+}
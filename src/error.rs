@@ -13,6 +13,11 @@ pub enum Error {
     InvalidOID,
     PrivateKeyMalformed,
     PublicKeyMalformed,
+    GroupMismatch,
+    UnknownRecipient,
+    InvalidShare,
+    InvalidState,
+    NotEnoughShares,
 }
 
 #[cfg(feature = "std")]
@@ -29,6 +34,11 @@ impl core::fmt::Display for Error {
             Error::InvalidOID => write!(f, "invalid OID"),
             Error::PrivateKeyMalformed => write!(f, "private key is malformed"),
             Error::PublicKeyMalformed => write!(f, "public key is malformed"),
+            Error::GroupMismatch => write!(f, "keys belong to different groups"),
+            Error::UnknownRecipient => write!(f, "key is not among the recipients"),
+            Error::InvalidShare => write!(f, "invalid share"),
+            Error::InvalidState => write!(f, "message not expected in the current state"),
+            Error::NotEnoughShares => write!(f, "not enough shares"),
         }
     }
 }
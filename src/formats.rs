@@ -1,3 +1,5 @@
+use std::collections::BTreeSet;
+
 use der::{
     asn1::{ObjectIdentifier, UIntRef},
     Decode, Encode, Sequence,
@@ -6,7 +8,10 @@ use num_bigint::BigUint;
 use num_traits::One;
 
 use crate::error::{Error, Result};
-use crate::{keys::ElgamalGroupElements, ElgamalGroup, ElgamalPrivateKey, ElgamalPublicKey};
+use crate::group::Group;
+use crate::{
+    keys::ElgamalGroupElements, ElgamalGroup, ElgamalPrivateKey, ElgamalPublicKey, PrivateKeyShare,
+};
 
 const ELGAMAL_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.14.7.2.1.1");
 const DSA_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10040.4.1");
@@ -20,11 +25,11 @@ fn verify_algorithm_id(oid: &ObjectIdentifier) -> bool {
 
 #[derive(Clone, Debug, Eq, PartialEq, Sequence)]
 pub struct GroupParams<'a> {
-    ///
+    /// Prime modulus
     pub p: UIntRef<'a>,
-    ///
+    /// Order of the subgroup generated by `g`
     pub q: Option<UIntRef<'a>>,
-    ///
+    /// Generator of the subgroup
     pub g: UIntRef<'a>,
 }
 
@@ -52,6 +57,42 @@ pub struct PrivateKeyInfo<'a> {
     pub x: &'a [u8],
 }
 
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+pub struct PrivateKeyShareInfo<'a> {
+    pub version: u8,
+    pub info: KeyInfo<'a>,
+    /// Index of the share, starting from 1
+    pub index: u32,
+    /// Number of shares required to recover the key
+    pub threshold: u32,
+    #[asn1(type = "OCTET STRING")]
+    pub share: &'a [u8],
+    /// Public exponent of the shared key
+    pub y: UIntRef<'a>,
+}
+
+/// Key identifier and b-component of every recipient.
+type Recipients = Vec<(Vec<u8>, BigUint)>;
+
+/// Share of a multi-recipient ciphertext addressed to a single key.
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+pub struct RecipientInfo<'a> {
+    /// Identifier of the recipient key, see [`ElgamalPublicKey::key_id`]
+    #[asn1(type = "OCTET STRING")]
+    pub key_id: &'a [u8],
+    /// b_i = m * y_i^r
+    pub b: UIntRef<'a>,
+}
+
+/// Multi-recipient ciphertext sharing a single `a = g^r`.
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+pub struct MultiRecipientInfo<'a> {
+    /// a = g^r
+    pub a: UIntRef<'a>,
+    /// One entry per recipient
+    pub recipients: Vec<RecipientInfo<'a>>,
+}
+
 impl TryFrom<PrivateKeyInfo<'_>> for ElgamalPrivateKey {
     type Error = Error;
 
@@ -76,6 +117,46 @@ impl TryFrom<PrivateKeyInfo<'_>> for ElgamalPrivateKey {
     }
 }
 
+impl TryFrom<PrivateKeyShareInfo<'_>> for PrivateKeyShare {
+    type Error = Error;
+
+    fn try_from(share_info: PrivateKeyShareInfo<'_>) -> Result<Self> {
+        if !verify_algorithm_id(&share_info.info.algorithm) {
+            return Err(Error::InvalidOID);
+        }
+
+        if share_info.version != 0 || share_info.index == 0 || share_info.threshold == 0 {
+            return Err(Error::PrivateKeyMalformed);
+        }
+
+        let p = BigUint::from_bytes_be(share_info.info.group_params.p.as_bytes());
+        let g = BigUint::from_bytes_be(share_info.info.group_params.g.as_bytes());
+        let q = match share_info.info.group_params.q {
+            None => return Err(Error::PrivateKeyMalformed),
+            Some(ref q) => BigUint::from_bytes_be(q.as_bytes()),
+        };
+
+        if p <= BigUint::one() || q <= BigUint::one() || BigUint::from(share_info.index) >= q {
+            return Err(Error::PrivateKeyMalformed);
+        }
+
+        let group = ElgamalGroup::new(p, q, g);
+        let share = BigUint::from_bytes_be(share_info.share);
+        let y = BigUint::from_bytes_be(share_info.y.as_bytes());
+        if share >= *group.get_q() || !group.is_element(&y) {
+            return Err(Error::PrivateKeyMalformed);
+        }
+
+        Ok(PrivateKeyShare::new(
+            group,
+            share_info.index,
+            share_info.threshold,
+            share,
+            y,
+        ))
+    }
+}
+
 impl TryFrom<PublicKeyInfo<'_>> for ElgamalPublicKey {
     type Error = Error;
 
@@ -149,6 +230,44 @@ pub fn private_key_encode(private_key: &ElgamalPrivateKey) -> Result<Vec<u8>> {
     Ok(data)
 }
 
+pub fn private_key_share_encode(share: &PrivateKeyShare) -> Result<Vec<u8>> {
+    let p = share.get_p().to_bytes_be();
+    let g = share.get_g().to_bytes_be();
+    let q = share.get_q().to_bytes_be();
+    let x = share.get_share().to_bytes_be();
+    let y = share.get_y().to_bytes_be();
+
+    let info = PrivateKeyShareInfo {
+        version: 0,
+        info: KeyInfo {
+            algorithm: ELGAMAL_OID,
+            group_params: GroupParams {
+                p: UIntRef::new(&p).map_err(|_| Error::InvalidData)?,
+                q: Some(UIntRef::new(&q).map_err(|_| Error::InvalidData)?),
+                g: UIntRef::new(&g).map_err(|_| Error::InvalidData)?,
+            },
+        },
+        index: share.index(),
+        threshold: share.threshold(),
+        share: &x,
+        y: UIntRef::new(&y).map_err(|_| Error::InvalidData)?,
+    };
+
+    let mut data = Vec::new();
+    let _len = info
+        .encode_to_vec(&mut data)
+        .map_err(|_| Error::InvalidData)?;
+
+    Ok(data)
+}
+
+pub fn private_key_share_decode(data: impl AsRef<[u8]>) -> Result<PrivateKeyShare> {
+    let share_info =
+        PrivateKeyShareInfo::from_der(data.as_ref()).map_err(|_| Error::PrivateKeyMalformed)?;
+
+    share_info.try_into()
+}
+
 pub fn public_key_decode(data: impl AsRef<[u8]>) -> Result<ElgamalPublicKey> {
     let pub_key_info =
         PublicKeyInfo::from_der(data.as_ref()).map_err(|_| Error::PublicKeyMalformed)?;
@@ -163,6 +282,55 @@ pub fn private_key_decode(data: impl AsRef<[u8]>) -> Result<ElgamalPrivateKey> {
     priv_key_info.try_into()
 }
 
+pub fn multi_recipient_encode(a: &BigUint, recipients: &[(Vec<u8>, BigUint)]) -> Result<Vec<u8>> {
+    let a = a.to_bytes_be();
+    let b: Vec<_> = recipients.iter().map(|(_, b)| b.to_bytes_be()).collect();
+
+    let info = MultiRecipientInfo {
+        a: UIntRef::new(&a).map_err(|_| Error::InvalidData)?,
+        recipients: recipients
+            .iter()
+            .zip(b.iter())
+            .map(|((key_id, _), b)| {
+                Ok(RecipientInfo {
+                    key_id,
+                    b: UIntRef::new(b).map_err(|_| Error::InvalidData)?,
+                })
+            })
+            .collect::<Result<_>>()?,
+    };
+
+    let mut data = Vec::new();
+    let _len = info
+        .encode_to_vec(&mut data)
+        .map_err(|_| Error::InvalidData)?;
+
+    Ok(data)
+}
+
+pub fn multi_recipient_decode(data: impl AsRef<[u8]>) -> Result<(BigUint, Recipients)> {
+    let info = MultiRecipientInfo::from_der(data.as_ref()).map_err(|_| Error::InvalidData)?;
+
+    let ids = info.recipients.iter().map(|recipient| recipient.key_id);
+    if ids.collect::<BTreeSet<_>>().len() != info.recipients.len() {
+        return Err(Error::InvalidData);
+    }
+
+    let a = BigUint::from_bytes_be(info.a.as_bytes());
+    let recipients = info
+        .recipients
+        .iter()
+        .map(|recipient| {
+            (
+                recipient.key_id.to_vec(),
+                BigUint::from_bytes_be(recipient.b.as_bytes()),
+            )
+        })
+        .collect();
+
+    Ok((a, recipients))
+}
+
 #[cfg(test)]
 mod test {
     use std::io::Read;
@@ -200,4 +368,37 @@ mod test {
         let priv_key = private_key_encode(&priv_key).unwrap();
         let _key = PrivateKeyInfo::from_der(priv_key.as_ref()).unwrap();
     }
+
+    #[test]
+    fn der_key_shares() {
+        let mut rng = StdRng::from_entropy();
+        let group = ElgamalGroup::generate(&mut rng, 256, 128);
+        let (_, priv_key) = elgamal_key_generate(&mut rng, &group);
+
+        let shares = priv_key.split(&mut rng, 2, 3).unwrap();
+        let decoded: Vec<_> = shares[1..]
+            .iter()
+            .map(|share| {
+                private_key_share_decode(private_key_share_encode(share).unwrap()).unwrap()
+            })
+            .collect();
+        assert_eq!(decoded, shares[1..]);
+
+        let recovered = crate::keys::elgamal_private_key_recover(&decoded).unwrap();
+        assert_eq!(recovered.get_x(), priv_key.get_x());
+
+        let malformed = |tamper: &dyn Fn(&mut PrivateKeyShare)| {
+            let mut share = shares[0].clone();
+            tamper(&mut share);
+            matches!(
+                private_key_share_decode(private_key_share_encode(&share).unwrap()),
+                Err(Error::PrivateKeyMalformed)
+            )
+        };
+        assert!(malformed(&|share| share.share = group.get_q().clone()));
+        assert!(malformed(&|share| share.y = group.get_p() - 1u32));
+        assert!(malformed(&|share| share.y = group.get_p().clone()));
+        assert!(malformed(&|share| share.index = 0));
+        assert!(malformed(&|share| share.threshold = 0));
+    }
 }
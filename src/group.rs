@@ -0,0 +1,264 @@
+use core::fmt::Debug;
+
+use digest::DynDigest;
+
+use num_bigint::{BigUint, ModInverse, RandBigInt};
+use num_traits::{One, Zero};
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::{Error, Result};
+use crate::keys::{ElgamalGroup, ElgamalGroupElements};
+
+/// Cyclic group of prime order `q` used by all algorithms of the crate.
+///
+/// Elements are written multiplicatively; scalars are exponents reduced modulo `q`.
+pub trait Group: Debug + Clone + PartialEq + Eq {
+    /// Element of the group
+    type Element: Debug + Clone + PartialEq + Eq;
+    /// Exponent modulo the order of the group
+    type Scalar: Debug + Clone + PartialEq + Eq;
+
+    /// Generator of the group.
+    fn generator(&self) -> Self::Element;
+    /// Neutral element of the group.
+    fn identity(&self) -> Self::Element;
+    /// Order `q` of the group.
+    fn order(&self) -> BigUint;
+
+    /// Product of two elements.
+    fn mul(&self, a: &Self::Element, b: &Self::Element) -> Self::Element;
+    /// Inverse of an element, `None` if `a` is not invertible.
+    fn invert(&self, a: &Self::Element) -> Option<Self::Element>;
+    /// `base^e`
+    fn exp(&self, base: &Self::Element, e: &Self::Scalar) -> Self::Element;
+    /// `g^e`
+    fn exp_generator(&self, e: &Self::Scalar) -> Self::Element {
+        self.exp(&self.generator(), e)
+    }
+    /// Checks that `a` belongs to the group of order `q`.
+    fn is_element(&self, a: &Self::Element) -> bool;
+
+    /// Size in bytes of an encoded element.
+    fn element_size(&self) -> usize;
+    /// Canonical encoding of an element, always `element_size()` bytes long.
+    fn element_to_bytes(&self, a: &Self::Element) -> Vec<u8>;
+    /// Decodes and validates an element.
+    fn element_from_bytes(&self, bytes: &[u8]) -> Result<Self::Element>;
+    /// Maps an element to a scalar, as required by the signature equation.
+    fn element_to_scalar(&self, a: &Self::Element) -> Self::Scalar;
+    /// Element derived from `input` whose discrete logarithm is unknown to everyone,
+    /// e.g. an independent generator for commitments.
+    fn hash_to_element(&self, digest: &mut dyn DynDigest, input: &[u8]) -> Self::Element;
+
+    /// Uniformly random scalar in `[1, q)`.
+    fn random_scalar<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Self::Scalar;
+    /// Reduces an integer modulo `q`.
+    fn scalar_from_biguint(&self, n: &BigUint) -> Self::Scalar;
+    /// Integer representative of a scalar in `[0, q)`.
+    fn scalar_to_biguint(&self, s: &Self::Scalar) -> BigUint;
+    fn scalar_from_u64(&self, n: u64) -> Self::Scalar {
+        self.scalar_from_biguint(&BigUint::from(n))
+    }
+    fn scalar_add(&self, a: &Self::Scalar, b: &Self::Scalar) -> Self::Scalar;
+    fn scalar_sub(&self, a: &Self::Scalar, b: &Self::Scalar) -> Self::Scalar;
+    fn scalar_mul(&self, a: &Self::Scalar, b: &Self::Scalar) -> Self::Scalar;
+    fn scalar_neg(&self, a: &Self::Scalar) -> Self::Scalar;
+    /// Multiplicative inverse modulo `q`, `None` for zero.
+    fn scalar_invert(&self, a: &Self::Scalar) -> Option<Self::Scalar>;
+
+    /// Size in bytes of an encoded scalar.
+    fn scalar_size(&self) -> usize;
+    /// Canonical encoding of a scalar, always `scalar_size()` bytes long.
+    fn scalar_to_bytes(&self, s: &Self::Scalar) -> Vec<u8>;
+    /// Decodes a scalar, rejecting values not below `q`.
+    fn scalar_from_bytes(&self, bytes: &[u8]) -> Result<Self::Scalar>;
+}
+
+/// Expands `input` into `size` pseudo-random bytes: `H(0 || input) || H(1 || input) || ...`
+pub(crate) fn expand_hash(digest: &mut dyn DynDigest, input: &[u8], size: usize) -> Vec<u8> {
+    let mut data = Vec::with_capacity(size + digest.output_size());
+    let mut counter = 0u32;
+    while data.len() < size {
+        digest.reset();
+        digest.update(&counter.to_be_bytes());
+        digest.update(input);
+        data.extend_from_slice(&digest.finalize_reset());
+        counter += 1;
+    }
+    data.truncate(size);
+
+    data
+}
+
+/// Left pads a big-endian integer with zeros up to `size` bytes.
+pub(crate) fn to_bytes_fixed(n: &BigUint, size: usize) -> Vec<u8> {
+    let bytes = n.to_bytes_be();
+    let mut data = vec![0u8; size.saturating_sub(bytes.len())];
+    data.extend_from_slice(&bytes);
+
+    data
+}
+
+/// Multiplicative subgroup of order `q` of Zp*.
+impl Group for ElgamalGroup {
+    type Element = BigUint;
+    type Scalar = BigUint;
+
+    fn generator(&self) -> BigUint {
+        self.get_g().clone()
+    }
+
+    fn identity(&self) -> BigUint {
+        BigUint::one()
+    }
+
+    fn order(&self) -> BigUint {
+        self.get_q().clone()
+    }
+
+    fn mul(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a * b % self.get_p()
+    }
+
+    fn invert(&self, a: &BigUint) -> Option<BigUint> {
+        a.mod_inverse(self.get_p())?.to_biguint()
+    }
+
+    fn exp(&self, base: &BigUint, e: &BigUint) -> BigUint {
+        base.modpow(e, self.get_p())
+    }
+
+    fn is_element(&self, a: &BigUint) -> bool {
+        !a.is_zero() && a < self.get_p() && a.modpow(self.get_q(), self.get_p()).is_one()
+    }
+
+    fn element_size(&self) -> usize {
+        (self.get_p().bits() + 7) / 8
+    }
+
+    fn element_to_bytes(&self, a: &BigUint) -> Vec<u8> {
+        to_bytes_fixed(a, self.element_size())
+    }
+
+    fn element_from_bytes(&self, bytes: &[u8]) -> Result<BigUint> {
+        if bytes.len() != self.element_size() {
+            return Err(Error::InvalidData);
+        }
+
+        let a = BigUint::from_bytes_be(bytes);
+        match self.is_element(&a) {
+            true => Ok(a),
+            false => Err(Error::InvalidData),
+        }
+    }
+
+    fn element_to_scalar(&self, a: &BigUint) -> BigUint {
+        a % self.get_q()
+    }
+
+    fn hash_to_element(&self, digest: &mut dyn DynDigest, input: &[u8]) -> BigUint {
+        let p = self.get_p();
+        let cofactor = (p - BigUint::one()) / self.get_q();
+        let mut data = input.to_vec();
+        loop {
+            // 128 extra bits make the reduction modulo p close to uniform.
+            let x = BigUint::from_bytes_be(&expand_hash(digest, &data, self.element_size() + 16));
+            let h = (x % p).modpow(&cofactor, p);
+            if !h.is_zero() && !h.is_one() {
+                break h;
+            }
+            data.push(0);
+        }
+    }
+
+    fn random_scalar<R: RngCore + CryptoRng>(&self, rng: &mut R) -> BigUint {
+        rng.gen_biguint_range(&BigUint::one(), self.get_q())
+    }
+
+    fn scalar_from_biguint(&self, n: &BigUint) -> BigUint {
+        n % self.get_q()
+    }
+
+    fn scalar_to_biguint(&self, s: &BigUint) -> BigUint {
+        s.clone()
+    }
+
+    fn scalar_add(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        (a + b) % self.get_q()
+    }
+
+    fn scalar_sub(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        let q = self.get_q();
+        (a % q + q - b % q) % q
+    }
+
+    fn scalar_mul(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a * b % self.get_q()
+    }
+
+    fn scalar_neg(&self, a: &BigUint) -> BigUint {
+        let q = self.get_q();
+        (q - a % q) % q
+    }
+
+    fn scalar_invert(&self, a: &BigUint) -> Option<BigUint> {
+        a.mod_inverse(self.get_q())?.to_biguint()
+    }
+
+    fn scalar_size(&self) -> usize {
+        (self.get_q().bits() + 7) / 8
+    }
+
+    fn scalar_to_bytes(&self, s: &BigUint) -> Vec<u8> {
+        to_bytes_fixed(s, self.scalar_size())
+    }
+
+    fn scalar_from_bytes(&self, bytes: &[u8]) -> Result<BigUint> {
+        if bytes.len() != self.scalar_size() {
+            return Err(Error::InvalidData);
+        }
+
+        let s = BigUint::from_bytes_be(bytes);
+        match s < *self.get_q() {
+            true => Ok(s),
+            false => Err(Error::InvalidRange),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{prelude::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn elgamal_group_encoding() {
+        let mut rng = StdRng::from_entropy();
+        let group = ElgamalGroup::generate(&mut rng, 256, 64);
+
+        let s = group.random_scalar(&mut rng);
+        let a = group.exp_generator(&s);
+        assert!(group.is_element(&a));
+
+        let bytes = group.element_to_bytes(&a);
+        assert_eq!(bytes.len(), group.element_size());
+        assert_eq!(group.element_from_bytes(&bytes).unwrap(), a);
+
+        let bytes = group.scalar_to_bytes(&s);
+        assert_eq!(bytes.len(), group.scalar_size());
+        assert_eq!(group.scalar_from_bytes(&bytes).unwrap(), s);
+
+        let inverse = group.invert(&a).unwrap();
+        assert_eq!(group.mul(&a, &inverse), group.identity());
+        assert_eq!(
+            group.scalar_add(&s, &group.scalar_neg(&s)),
+            group.scalar_from_u64(0)
+        );
+
+        // Unreduced operands, e.g. from decoded keys, are reduced first.
+        let big = &s + group.get_q() * 3u32;
+        assert_eq!(group.scalar_sub(&s, &big), group.scalar_from_u64(0));
+        assert_eq!(group.scalar_neg(&big), group.scalar_neg(&s));
+    }
+}
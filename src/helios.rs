@@ -0,0 +1,502 @@
+use digest::DynDigest;
+use num_bigint::BigUint;
+use num_traits::Num;
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::group::Group;
+use crate::keys::{ElgamalGroup, ElgamalGroupElements, ElgamalPrivateKey, ElgamalPublicKey};
+
+/// Helios election public key, integers written in decimal.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeliosPublicKey {
+    pub g: String,
+    pub p: String,
+    pub q: String,
+    pub y: String,
+}
+
+/// Helios ciphertext `(alpha, beta) = (g^r, g^m * y^r)`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeliosCiphertext {
+    pub alpha: String,
+    pub beta: String,
+}
+
+/// Commitments `(A, B)` of a Helios Chaum–Pedersen proof.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeliosCommitment {
+    #[serde(rename = "A")]
+    pub a: String,
+    #[serde(rename = "B")]
+    pub b: String,
+}
+
+/// Helios Chaum–Pedersen proof, one branch of a disjunctive proof.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeliosProof {
+    pub challenge: String,
+    pub commitment: HeliosCommitment,
+    pub response: String,
+}
+
+/// Encrypted answer to a question of a Helios ballot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeliosEncryptedAnswer {
+    /// One ciphertext per choice
+    pub choices: Vec<HeliosCiphertext>,
+    /// 0/1 proof of every choice
+    pub individual_proofs: Vec<Vec<HeliosProof>>,
+    /// Proof that the sum of the choices is between `min` and `max`
+    pub overall_proof: Vec<HeliosProof>,
+}
+
+/// Partial decryptions of a trustee for every choice of every question.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeliosDecryptionFactors {
+    pub decryption_factors: Vec<Vec<String>>,
+    pub decryption_proofs: Vec<Vec<HeliosProof>>,
+}
+
+fn parse(s: &str) -> Result<BigUint> {
+    BigUint::from_str_radix(s, 10).map_err(|_| Error::InvalidData)
+}
+
+fn format(n: &BigUint) -> String {
+    n.to_str_radix(10)
+}
+
+fn parse_element(group: &ElgamalGroup, s: &str) -> Result<BigUint> {
+    let e = parse(s)?;
+    match group.is_element(&e) {
+        true => Ok(e),
+        false => Err(Error::InvalidData),
+    }
+}
+
+impl HeliosPublicKey {
+    /// Validates the group and the key.
+    pub fn to_public_key(&self) -> Result<ElgamalPublicKey> {
+        let group = ElgamalGroup::new(parse(&self.p)?, parse(&self.q)?, parse(&self.g)?);
+        let (p, q) = (group.get_p(), group.get_q());
+        if q.bits() < 2 || (p - 1u32) % q != BigUint::from(0u32) {
+            return Err(Error::PublicKeyMalformed);
+        }
+        if !group.is_element(group.get_g()) || *group.get_g() == BigUint::from(1u32) {
+            return Err(Error::PublicKeyMalformed);
+        }
+        let y = parse_element(&group, &self.y).map_err(|_| Error::PublicKeyMalformed)?;
+
+        Ok(ElgamalPublicKey::new(group, y))
+    }
+
+    pub fn from_public_key(key: &ElgamalPublicKey) -> Self {
+        Self {
+            g: format(key.get_g()),
+            p: format(key.get_p()),
+            q: format(key.get_q()),
+            y: format(key.get_y()),
+        }
+    }
+}
+
+impl HeliosCiphertext {
+    /// Validates both components.
+    pub fn to_ciphertext(&self, key: &ElgamalPublicKey) -> Result<(BigUint, BigUint)> {
+        Ok((
+            parse_element(key.group(), &self.alpha)?,
+            parse_element(key.group(), &self.beta)?,
+        ))
+    }
+
+    pub fn from_ciphertext((alpha, beta): (&BigUint, &BigUint)) -> Self {
+        Self {
+            alpha: format(alpha),
+            beta: format(beta),
+        }
+    }
+}
+
+/// Decodes a Helios public key from JSON.
+pub fn helios_public_key_decode(json: &str) -> Result<ElgamalPublicKey> {
+    let key: HeliosPublicKey = serde_json::from_str(json).map_err(|_| Error::InvalidData)?;
+
+    key.to_public_key()
+}
+
+/// Encodes a public key as Helios JSON.
+pub fn helios_public_key_encode(key: &ElgamalPublicKey) -> Result<String> {
+    serde_json::to_string(&HeliosPublicKey::from_public_key(key)).map_err(|_| Error::InvalidData)
+}
+
+/// Helios challenge: `SHA1("A0,B0,A1,B1,...")` over decimal strings, as an integer.
+/// `digest` must be SHA-1 for Helios compatibility.
+fn challenge(digest: &mut dyn DynDigest, commitments: &[&HeliosCommitment]) -> BigUint {
+    let data = commitments
+        .iter()
+        .flat_map(|commitment| [commitment.a.as_str(), commitment.b.as_str()])
+        .collect::<Vec<_>>()
+        .join(",");
+
+    digest.reset();
+    digest.update(data.as_bytes());
+    BigUint::from_bytes_be(&digest.finalize_reset())
+}
+
+/// Checks `g1^response == A * h1^challenge` and `g2^response == B * h2^challenge`.
+fn verify_branch(
+    group: &ElgamalGroup,
+    proof: &HeliosProof,
+    (g1, h1): (&BigUint, &BigUint),
+    (g2, h2): (&BigUint, &BigUint),
+) -> Result<BigUint> {
+    let c = parse(&proof.challenge)?;
+    let s = parse(&proof.response)?;
+    let a = parse_element(group, &proof.commitment.a)?;
+    let b = parse_element(group, &proof.commitment.b)?;
+    let p = group.get_p();
+
+    let valid = g1.modpow(&s, p) == h1.modpow(&c, p) * a % p
+        && g2.modpow(&s, p) == h2.modpow(&c, p) * b % p;
+
+    match valid {
+        true => Ok(c),
+        false => Err(Error::Verification),
+    }
+}
+
+/// `beta / g^m` for every plaintext of `[min, max]`.
+fn quotients(group: &ElgamalGroup, beta: &BigUint, min: u64, max: u64) -> Result<Vec<BigUint>> {
+    (min..=max)
+        .map(|m| {
+            let gm = group.exp_generator(&group.scalar_from_u64(m));
+            Ok(group.mul(beta, &group.invert(&gm).ok_or(Error::InvalidInverse)?))
+        })
+        .collect()
+}
+
+/// Verifies a Helios disjunctive proof that `(alpha, beta)` encrypts `g^m` for some
+/// `m` in `[min, max]`.
+pub fn helios_verify_disjunctive(
+    digest: &mut dyn DynDigest,
+    key: &ElgamalPublicKey,
+    (alpha, beta): (&BigUint, &BigUint),
+    proofs: &[HeliosProof],
+    min: u64,
+    max: u64,
+) -> Result<()> {
+    if min > max || proofs.len() as u64 != max - min + 1 {
+        return Err(Error::InvalidData);
+    }
+
+    let group = key.group();
+    let g = group.get_g();
+    let mut sum = BigUint::from(0u32);
+    for (proof, quotient) in proofs.iter().zip(quotients(group, beta, min, max)?) {
+        sum += verify_branch(group, proof, (g, alpha), (key.get_y(), &quotient))?;
+    }
+
+    let commitments: Vec<_> = proofs.iter().map(|proof| &proof.commitment).collect();
+    match sum % group.get_q() == challenge(digest, &commitments) % group.get_q() {
+        true => Ok(()),
+        false => Err(Error::Verification),
+    }
+}
+
+/// Produces a Helios disjunctive proof that `(alpha, beta)`, encrypted with randomness
+/// `r`, encrypts `g^m` with `m` in `[min, max]`.
+#[allow(clippy::too_many_arguments)]
+pub fn helios_prove_disjunctive<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    digest: &mut dyn DynDigest,
+    key: &ElgamalPublicKey,
+    (alpha, beta): (&BigUint, &BigUint),
+    r: &BigUint,
+    m: u64,
+    min: u64,
+    max: u64,
+) -> Result<Vec<HeliosProof>> {
+    if m < min || m > max {
+        return Err(Error::InvalidRange);
+    }
+
+    let group = key.group();
+    let (p, q) = (group.get_p(), group.get_q());
+    let mut branches = Vec::new();
+    let w = group.random_scalar(rng);
+    for (value, quotient) in (min..=max).zip(quotients(group, beta, min, max)?) {
+        if value == m {
+            branches.push((
+                BigUint::from(0u32),
+                BigUint::from(0u32),
+                group.exp_generator(&w),
+                key.get_y().modpow(&w, p),
+            ));
+        } else {
+            // Simulated branch: A = g^s / alpha^c, B = y^s / (beta / g^v)^c.
+            let c = group.random_scalar(rng);
+            let s = group.random_scalar(rng);
+            let a = group.mul(
+                &group.exp_generator(&s),
+                &group
+                    .invert(&alpha.modpow(&c, p))
+                    .ok_or(Error::InvalidInverse)?,
+            );
+            let b = group.mul(
+                &key.get_y().modpow(&s, p),
+                &group
+                    .invert(&quotient.modpow(&c, p))
+                    .ok_or(Error::InvalidInverse)?,
+            );
+            branches.push((c, s, a, b));
+        }
+    }
+
+    let mut proofs: Vec<_> = branches
+        .iter()
+        .map(|(c, s, a, b)| HeliosProof {
+            challenge: format(c),
+            commitment: HeliosCommitment {
+                a: format(a),
+                b: format(b),
+            },
+            response: format(s),
+        })
+        .collect();
+
+    let commitments: Vec<_> = proofs.iter().map(|proof| &proof.commitment).collect();
+    let c = challenge(digest, &commitments) % q;
+    let simulated = branches
+        .iter()
+        .fold(BigUint::from(0u32), |acc, (c, _, _, _)| acc + c);
+    let real = group.scalar_sub(&c, &(simulated % q));
+    let response = group.scalar_add(&w, &group.scalar_mul(&real, r));
+
+    let index = (m - min) as usize;
+    proofs[index].challenge = format(&real);
+    proofs[index].response = format(&response);
+
+    Ok(proofs)
+}
+
+/// Verifies an encrypted answer: every choice encrypts 0 or 1 and their sum is in
+/// `[min, max]`.
+pub fn helios_verify_answer(
+    digest: &mut dyn DynDigest,
+    key: &ElgamalPublicKey,
+    answer: &HeliosEncryptedAnswer,
+    min: u64,
+    max: u64,
+) -> Result<()> {
+    if answer.choices.is_empty() || answer.choices.len() != answer.individual_proofs.len() {
+        return Err(Error::InvalidData);
+    }
+
+    let group = key.group();
+    let mut sum = (group.identity(), group.identity());
+    for (choice, proofs) in answer.choices.iter().zip(&answer.individual_proofs) {
+        let (alpha, beta) = choice.to_ciphertext(key)?;
+        helios_verify_disjunctive(digest, key, (&alpha, &beta), proofs, 0, 1)?;
+        sum = (group.mul(&sum.0, &alpha), group.mul(&sum.1, &beta));
+    }
+
+    helios_verify_disjunctive(
+        digest,
+        key,
+        (&sum.0, &sum.1),
+        &answer.overall_proof,
+        min,
+        max,
+    )
+}
+
+/// Verifies the partial decryption `factor = alpha^x` of a trustee with public key
+/// `trustee`: `log_g y = log_alpha factor` with the Helios Fiat–Shamir challenge.
+pub fn helios_verify_decryption_factor(
+    digest: &mut dyn DynDigest,
+    trustee: &ElgamalPublicKey,
+    alpha: &BigUint,
+    factor: &BigUint,
+    proof: &HeliosProof,
+) -> Result<()> {
+    let group = trustee.group();
+    if !group.is_element(alpha) || !group.is_element(factor) {
+        return Err(Error::InvalidData);
+    }
+
+    let c = verify_branch(
+        group,
+        proof,
+        (group.get_g(), trustee.get_y()),
+        (alpha, factor),
+    )?;
+    match c == challenge(digest, &[&proof.commitment]) {
+        true => Ok(()),
+        false => Err(Error::Verification),
+    }
+}
+
+/// Partial decryption `alpha^x` of a trustee with its Helios proof.
+pub fn helios_decryption_factor<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    digest: &mut dyn DynDigest,
+    trustee: &ElgamalPrivateKey,
+    alpha: &BigUint,
+) -> Result<(BigUint, HeliosProof)> {
+    let group = trustee.group();
+    if !group.is_element(alpha) {
+        return Err(Error::InvalidData);
+    }
+
+    let w = group.random_scalar(rng);
+    let commitment = HeliosCommitment {
+        a: format(&group.exp_generator(&w)),
+        b: format(&group.exp(alpha, &w)),
+    };
+    let c = challenge(digest, &[&commitment]);
+    let s = group.scalar_add(
+        &w,
+        &group.scalar_mul(&group.scalar_from_biguint(&c), trustee.get_x()),
+    );
+
+    Ok((
+        group.exp(alpha, trustee.get_x()),
+        HeliosProof {
+            challenge: format(&c),
+            commitment,
+            response: format(&s),
+        },
+    ))
+}
+
+/// Verifies all decryption factors of a trustee against the tallies
+/// `alphas[question][choice]`.
+pub fn helios_verify_decryption_factors(
+    digest: &mut dyn DynDigest,
+    trustee: &ElgamalPublicKey,
+    alphas: &[Vec<BigUint>],
+    factors: &HeliosDecryptionFactors,
+) -> Result<()> {
+    if alphas.len() != factors.decryption_factors.len()
+        || alphas.len() != factors.decryption_proofs.len()
+    {
+        return Err(Error::InvalidData);
+    }
+
+    for ((alphas, factors), proofs) in alphas
+        .iter()
+        .zip(&factors.decryption_factors)
+        .zip(&factors.decryption_proofs)
+    {
+        if alphas.len() != factors.len() || alphas.len() != proofs.len() {
+            return Err(Error::InvalidData);
+        }
+        for ((alpha, factor), proof) in alphas.iter().zip(factors).zip(proofs) {
+            helios_verify_decryption_factor(digest, trustee, alpha, &parse(factor)?, proof)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{prelude::StdRng, SeedableRng};
+    use sha1::{Digest, Sha1};
+
+    use super::*;
+    use crate::elgamal_key_generate;
+    use crate::internal::group_encrypt_exponent_with;
+
+    #[test]
+    fn helios_public_key() {
+        let json = r#"{"g": "4", "p": "23", "q": "11", "y": "13"}"#;
+        let key = helios_public_key_decode(json).unwrap();
+        assert_eq!(key.get_y(), &BigUint::from(13u32));
+
+        let encoded = helios_public_key_encode(&key).unwrap();
+        assert_eq!(helios_public_key_decode(&encoded).unwrap(), key);
+
+        // 5 is not in the subgroup of order 11.
+        let json = r#"{"g": "4", "p": "23", "q": "11", "y": "5"}"#;
+        assert!(helios_public_key_decode(json).is_err());
+    }
+
+    #[test]
+    fn helios_answer() {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha1::new();
+        let group = ElgamalGroup::generate(&mut rng, 256, 128);
+        let (key, _) = elgamal_key_generate(&mut rng, &group);
+
+        let mut answer = HeliosEncryptedAnswer {
+            choices: vec![],
+            individual_proofs: vec![],
+            overall_proof: vec![],
+        };
+        let mut total = BigUint::from(0u32);
+        for m in [0u64, 1, 0] {
+            let r = group.random_scalar(&mut rng);
+            let (alpha, beta) =
+                group_encrypt_exponent_with(&group, key.get_y(), &group.scalar_from_u64(m), &r);
+            let proofs =
+                helios_prove_disjunctive(&mut rng, &mut digest, &key, (&alpha, &beta), &r, m, 0, 1)
+                    .unwrap();
+            answer
+                .choices
+                .push(HeliosCiphertext::from_ciphertext((&alpha, &beta)));
+            answer.individual_proofs.push(proofs);
+            total = group.scalar_add(&total, &r);
+        }
+
+        let sum = answer
+            .choices
+            .iter()
+            .fold((group.identity(), group.identity()), |acc, c| {
+                let (alpha, beta) = c.to_ciphertext(&key).unwrap();
+                (group.mul(&acc.0, &alpha), group.mul(&acc.1, &beta))
+            });
+        answer.overall_proof = helios_prove_disjunctive(
+            &mut rng,
+            &mut digest,
+            &key,
+            (&sum.0, &sum.1),
+            &total,
+            1,
+            0,
+            1,
+        )
+        .unwrap();
+
+        let json = serde_json::to_string(&answer).unwrap();
+        let answer: HeliosEncryptedAnswer = serde_json::from_str(&json).unwrap();
+        helios_verify_answer(&mut digest, &key, &answer, 0, 1).unwrap();
+        assert!(helios_verify_answer(&mut digest, &key, &answer, 2, 3).is_err());
+    }
+
+    #[test]
+    fn helios_trustee() {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha1::new();
+        let group = ElgamalGroup::generate(&mut rng, 256, 128);
+        let (public, private) = elgamal_key_generate(&mut rng, &group);
+
+        let alphas = vec![vec![
+            group.exp_generator(&group.random_scalar(&mut rng)),
+            group.exp_generator(&group.random_scalar(&mut rng)),
+        ]];
+        let (factors, proofs): (Vec<_>, Vec<_>) = alphas[0]
+            .iter()
+            .map(|alpha| helios_decryption_factor(&mut rng, &mut digest, &private, alpha).unwrap())
+            .unzip();
+        let mut factors = HeliosDecryptionFactors {
+            decryption_factors: vec![factors.iter().map(format).collect()],
+            decryption_proofs: vec![proofs],
+        };
+        helios_verify_decryption_factors(&mut digest, &public, &alphas, &factors).unwrap();
+
+        factors.decryption_factors[0].swap(0, 1);
+        assert!(helios_verify_decryption_factors(&mut digest, &public, &alphas, &factors).is_err());
+    }
+}
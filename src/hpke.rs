@@ -0,0 +1,343 @@
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use rand_core::{CryptoRng, RngCore};
+use sha2::Sha256;
+
+use crate::error::{Error, Result};
+use crate::group::Group;
+use crate::keys::{
+    elgamal_key_generate, shared_secret, ElgamalGroup, ElgamalPrivateKey, ElgamalPublicKey,
+};
+
+/// Identifier of the finite-field DHKEM, from the private-use range since no
+/// finite-field KEM is registered for HPKE.
+const KEM_ID: u16 = 0xff01;
+/// HKDF-SHA256
+const KDF_ID: u16 = 0x0001;
+/// ChaCha20Poly1305
+const AEAD_ID: u16 = 0x0003;
+
+/// Size of the KEM shared secret and of the KDF output.
+const NH: usize = 32;
+/// Size of the AEAD key.
+const NK: usize = 32;
+/// Size of the AEAD nonce.
+const NN: usize = 12;
+
+/// Mode of an HPKE context, as defined by RFC 9180.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HpkeMode {
+    Base = 0x00,
+    Psk = 0x01,
+    Auth = 0x02,
+    AuthPsk = 0x03,
+}
+
+/// Pre-shared key and its identifier, for the PSK modes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HpkePsk<'a> {
+    pub psk: &'a [u8],
+    pub id: &'a [u8],
+}
+
+/// Encryption context shared by sender and recipient: both sides must seal and
+/// open messages in the same order.
+#[derive(Clone)]
+pub struct HpkeContext {
+    mode: HpkeMode,
+    key: Vec<u8>,
+    base_nonce: Vec<u8>,
+    exporter_secret: Vec<u8>,
+    seq: u64,
+}
+
+impl core::fmt::Debug for HpkeContext {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("HpkeContext")
+            .field("mode", &self.mode)
+            .field("seq", &self.seq)
+            .finish()
+    }
+}
+
+fn kem_suite_id() -> Vec<u8> {
+    let mut id = b"KEM".to_vec();
+    id.extend_from_slice(&KEM_ID.to_be_bytes());
+    id
+}
+
+fn hpke_suite_id() -> Vec<u8> {
+    let mut id = b"HPKE".to_vec();
+    for part in [KEM_ID, KDF_ID, AEAD_ID] {
+        id.extend_from_slice(&part.to_be_bytes());
+    }
+    id
+}
+
+/// `HKDF-Extract(salt, "HPKE-v1" || suite_id || label || ikm)`
+fn labeled_extract(suite_id: &[u8], salt: &[u8], label: &[u8], ikm: &[u8]) -> Vec<u8> {
+    let labeled_ikm = [b"HPKE-v1", suite_id, label, ikm].concat();
+    let (prk, _) = Hkdf::<Sha256>::extract(Some(salt), &labeled_ikm);
+
+    prk.to_vec()
+}
+
+/// `HKDF-Expand(prk, I2OSP(L, 2) || "HPKE-v1" || suite_id || label || info, L)`
+fn labeled_expand(
+    suite_id: &[u8],
+    prk: &[u8],
+    label: &[u8],
+    info: &[u8],
+    size: usize,
+) -> Result<Vec<u8>> {
+    let length = u16::try_from(size).map_err(|_| Error::InvalidRange)?;
+    let hkdf = Hkdf::<Sha256>::from_prk(prk).map_err(|_| Error::InvalidData)?;
+    let mut okm = vec![0u8; size];
+    hkdf.expand_multi_info(
+        &[&length.to_be_bytes(), b"HPKE-v1", suite_id, label, info],
+        &mut okm,
+    )
+    .map_err(|_| Error::InvalidRange)?;
+
+    Ok(okm)
+}
+
+fn serialize(key: &ElgamalPublicKey) -> Vec<u8> {
+    key.group().element_to_bytes(key.get_y())
+}
+
+fn deserialize(group: &ElgamalGroup, bytes: &[u8]) -> Result<ElgamalPublicKey> {
+    let y = group
+        .element_from_bytes(bytes)
+        .map_err(|_| Error::PublicKeyMalformed)?;
+
+    Ok(ElgamalPublicKey::new(group.clone(), y))
+}
+
+fn extract_and_expand(dh: &[u8], kem_context: &[u8]) -> Result<Vec<u8>> {
+    let suite_id = kem_suite_id();
+    let eae_prk = labeled_extract(&suite_id, b"", b"eae_prk", dh);
+
+    labeled_expand(&suite_id, &eae_prk, b"shared_secret", kem_context, NH)
+}
+
+fn mode(psk: Option<&HpkePsk>, authenticated: bool) -> HpkeMode {
+    match (psk.is_some(), authenticated) {
+        (false, false) => HpkeMode::Base,
+        (true, false) => HpkeMode::Psk,
+        (false, true) => HpkeMode::Auth,
+        (true, true) => HpkeMode::AuthPsk,
+    }
+}
+
+fn key_schedule(
+    mode: HpkeMode,
+    shared_secret: &[u8],
+    info: &[u8],
+    psk: Option<&HpkePsk>,
+) -> Result<HpkeContext> {
+    let (psk, psk_id) = match psk {
+        Some(HpkePsk { psk, id }) if psk.is_empty() || id.is_empty() => {
+            return Err(Error::InvalidData)
+        }
+        Some(HpkePsk { psk, id }) => (*psk, *id),
+        None => (&[][..], &[][..]),
+    };
+
+    let suite_id = hpke_suite_id();
+    let psk_id_hash = labeled_extract(&suite_id, b"", b"psk_id_hash", psk_id);
+    let info_hash = labeled_extract(&suite_id, b"", b"info_hash", info);
+    let context = [&[mode as u8][..], &psk_id_hash, &info_hash].concat();
+
+    let secret = labeled_extract(&suite_id, shared_secret, b"secret", psk);
+
+    Ok(HpkeContext {
+        mode,
+        key: labeled_expand(&suite_id, &secret, b"key", &context, NK)?,
+        base_nonce: labeled_expand(&suite_id, &secret, b"base_nonce", &context, NN)?,
+        exporter_secret: labeled_expand(&suite_id, &secret, b"exp", &context, NH)?,
+        seq: 0,
+    })
+}
+
+/// Sets up a context to `recipient`, returning the encapsulated key `enc` to send
+/// along. The mode follows from the arguments: a `psk` selects a PSK mode and a
+/// `sender` key authenticates the sender.
+pub fn hpke_setup_sender<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    recipient: &ElgamalPublicKey,
+    info: &[u8],
+    psk: Option<&HpkePsk>,
+    sender: Option<&ElgamalPrivateKey>,
+) -> Result<(Vec<u8>, HpkeContext)> {
+    let (ephemeral_public, ephemeral) = elgamal_key_generate(rng, recipient.group());
+    let enc = serialize(&ephemeral_public);
+
+    let mut dh_value = shared_secret(&ephemeral, recipient)?;
+    let mut kem_context = [&enc[..], &serialize(recipient)].concat();
+    if let Some(sender) = sender {
+        dh_value.extend_from_slice(&shared_secret(sender, recipient)?);
+        kem_context.extend_from_slice(&serialize(&sender.to_public_key()));
+    }
+
+    let shared_secret = extract_and_expand(&dh_value, &kem_context)?;
+    let context = key_schedule(mode(psk, sender.is_some()), &shared_secret, info, psk)?;
+
+    Ok((enc, context))
+}
+
+/// Sets up the recipient context matching [`hpke_setup_sender`]; `sender` is the
+/// public key expected to have authenticated the context.
+pub fn hpke_setup_receiver(
+    enc: &[u8],
+    recipient: &ElgamalPrivateKey,
+    info: &[u8],
+    psk: Option<&HpkePsk>,
+    sender: Option<&ElgamalPublicKey>,
+) -> Result<HpkeContext> {
+    let ephemeral = deserialize(recipient.group(), enc)?;
+
+    let mut dh_value = shared_secret(recipient, &ephemeral)?;
+    let mut kem_context = [enc, &serialize(&recipient.to_public_key())].concat();
+    if let Some(sender) = sender {
+        dh_value.extend_from_slice(&shared_secret(recipient, sender)?);
+        kem_context.extend_from_slice(&serialize(sender));
+    }
+
+    let shared_secret = extract_and_expand(&dh_value, &kem_context)?;
+    key_schedule(mode(psk, sender.is_some()), &shared_secret, info, psk)
+}
+
+impl HpkeContext {
+    /// Returns the mode of the context.
+    pub fn mode(&self) -> HpkeMode {
+        self.mode
+    }
+
+    /// Nonce of the current message, `base_nonce XOR seq`.
+    fn nonce(&self) -> Vec<u8> {
+        let mut nonce = self.base_nonce.clone();
+        for (n, s) in nonce[NN - 8..].iter_mut().zip(self.seq.to_be_bytes()) {
+            *n ^= s;
+        }
+        nonce
+    }
+
+    fn increment(&mut self) -> Result<()> {
+        self.seq = self.seq.checked_add(1).ok_or(Error::InvalidState)?;
+        Ok(())
+    }
+
+    /// Encrypts the next message.
+    pub fn seal(&mut self, aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&self.key));
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&self.nonce()),
+                Payload {
+                    msg: plaintext,
+                    aad,
+                },
+            )
+            .map_err(|_| Error::MessageTooLong)?;
+        self.increment()?;
+
+        Ok(ciphertext)
+    }
+
+    /// Decrypts the next message.
+    pub fn open(&mut self, aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&self.key));
+        let plaintext = cipher
+            .decrypt(
+                Nonce::from_slice(&self.nonce()),
+                Payload {
+                    msg: ciphertext,
+                    aad,
+                },
+            )
+            .map_err(|_| Error::Verification)?;
+        self.increment()?;
+
+        Ok(plaintext)
+    }
+
+    /// Derives `size` bytes bound to the context and to `exporter_context`.
+    pub fn export(&self, exporter_context: &[u8], size: usize) -> Result<Vec<u8>> {
+        labeled_expand(
+            &hpke_suite_id(),
+            &self.exporter_secret,
+            b"sec",
+            exporter_context,
+            size,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{prelude::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn hpke_modes() {
+        let mut rng = StdRng::from_entropy();
+        let group = ElgamalGroup::generate(&mut rng, 256, 128);
+        let (recipient_pub, recipient) = elgamal_key_generate(&mut rng, &group);
+        let (sender_pub, sender) = elgamal_key_generate(&mut rng, &group);
+        let (other_pub, _) = elgamal_key_generate(&mut rng, &group);
+        let psk = HpkePsk {
+            psk: b"0123456789abcdef0123456789abcdef",
+            id: b"psk 1",
+        };
+        let wrong_psk = HpkePsk {
+            id: b"psk 2",
+            ..psk
+        };
+
+        for (psk, auth, expected) in [
+            (None, false, HpkeMode::Base),
+            (Some(&psk), false, HpkeMode::Psk),
+            (None, true, HpkeMode::Auth),
+            (Some(&psk), true, HpkeMode::AuthPsk),
+        ] {
+            let (enc, mut sealer) = hpke_setup_sender(
+                &mut rng,
+                &recipient_pub,
+                b"info",
+                psk,
+                auth.then_some(&sender),
+            )
+            .unwrap();
+            let mut opener =
+                hpke_setup_receiver(&enc, &recipient, b"info", psk, auth.then_some(&sender_pub))
+                    .unwrap();
+            assert_eq!(sealer.mode(), expected);
+
+            for msg in [&b"first"[..], b"second", b""] {
+                let ct = sealer.seal(b"aad", msg).unwrap();
+                assert_eq!(opener.open(b"aad", &ct).unwrap(), msg);
+            }
+            assert_eq!(
+                sealer.export(b"exporter", 40).unwrap(),
+                opener.export(b"exporter", 40).unwrap()
+            );
+
+            let ct = sealer.seal(b"aad", b"message").unwrap();
+            assert!(opener.clone().open(b"other", &ct).is_err());
+
+            let mut wrong = match (psk, auth) {
+                (_, true) => hpke_setup_receiver(&enc, &recipient, b"info", psk, Some(&other_pub)),
+                (Some(_), false) => {
+                    hpke_setup_receiver(&enc, &recipient, b"info", Some(&wrong_psk), None)
+                }
+                (None, false) => hpke_setup_receiver(&enc, &recipient, b"other", None, None),
+            }
+            .unwrap();
+            wrong.seq = opener.seq;
+            assert!(wrong.open(b"aad", &ct).is_err());
+        }
+    }
+}
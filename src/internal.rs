@@ -1,37 +1,126 @@
 use num_bigint::BigUint;
-use num_bigint::ModInverse;
-use num_bigint::RandBigInt;
-use num_traits::One;
 use rand_core::{CryptoRng, RngCore};
 
 use crate::error::{Error, Result};
-use crate::keys::{ElgamalGroupElements, ElgamalPrivateKey, ElgamalPublicKey};
+use crate::group::Group;
+use crate::keys::{ElgamalGroup, ElgamalGroupElements, ElgamalPrivateKey, ElgamalPublicKey};
+use crate::proofs::DecryptionProof;
+use crate::transcript::Transcript;
 
 use digest::DynDigest;
 
+/// El Gamal encryption of `m` with the given randomness: `(g^r, m * y^r)`
 #[inline]
-fn encrypt_raw(
-    m: &BigUint,
-    p: &BigUint,
-    y: &BigUint,
-    g: &BigUint,
-    r: &BigUint,
-) -> (BigUint, BigUint) {
-    (g.modpow(r, p), m * y.modpow(r, p))
+pub(crate) fn group_encrypt_with<G: Group>(
+    group: &G,
+    y: &G::Element,
+    m: &G::Element,
+    r: &G::Scalar,
+) -> (G::Element, G::Element) {
+    (group.exp_generator(r), group.mul(m, &group.exp(y, r)))
+}
+
+/// El Gamal encryption of an element of any [`Group`]
+#[inline]
+pub fn group_encrypt<G: Group, R: RngCore + CryptoRng>(
+    rng: &mut R,
+    group: &G,
+    y: &G::Element,
+    m: &G::Element,
+) -> (G::Element, G::Element) {
+    let r = group.random_scalar(rng);
+
+    group_encrypt_with(group, y, m, &r)
+}
+
+/// Re-randomization of a ciphertext of any [`Group`]
+#[inline]
+pub fn group_reencrypt<G: Group, R: RngCore + CryptoRng>(
+    rng: &mut R,
+    group: &G,
+    y: &G::Element,
+    a: &G::Element,
+    b: &G::Element,
+) -> (G::Element, G::Element) {
+    let r = group.random_scalar(rng);
+
+    let (a1, b1) = group_encrypt_with(group, y, b, &r);
+
+    (group.mul(a, &a1), b1)
+}
+
+/// El Gamal decryption in any [`Group`]: `b / a^x`
+#[inline]
+pub fn group_decrypt<G: Group>(
+    group: &G,
+    x: &G::Scalar,
+    a: &G::Element,
+    b: &G::Element,
+) -> Result<G::Element> {
+    let divider = group
+        .invert(&group.exp(a, x))
+        .ok_or(Error::InvalidPrivateKey)?;
+
+    Ok(group.mul(b, &divider))
+}
+
+/// Exponential ("lifted") El Gamal encryption of `v` with the given randomness:
+/// `(g^r, g^v * y^r)`. Ciphertexts are additively homomorphic in `v`.
+#[inline]
+pub fn group_encrypt_exponent_with<G: Group>(
+    group: &G,
+    y: &G::Element,
+    v: &G::Scalar,
+    r: &G::Scalar,
+) -> (G::Element, G::Element) {
+    group_encrypt_with(group, y, &group.exp_generator(v), r)
+}
+
+/// Component-wise product of two ciphertexts, an encryption of the product of the
+/// plaintexts (of the sum of `v` for exponential El Gamal).
+#[inline]
+pub fn group_ciphertext_mul<G: Group>(
+    group: &G,
+    (a1, b1): (&G::Element, &G::Element),
+    (a2, b2): (&G::Element, &G::Element),
+) -> (G::Element, G::Element) {
+    (group.mul(a1, a2), group.mul(b1, b2))
+}
+
+/// El Gamal signature of the scalar `h` in any [`Group`]
+#[inline]
+pub fn group_sign<G: Group, R: RngCore + CryptoRng>(
+    rng: &mut R,
+    group: &G,
+    x: &G::Scalar,
+    h: &G::Scalar,
+) -> Result<(G::Element, G::Scalar)> {
+    let k = group.random_scalar(rng);
+    let r = group.exp_generator(&k);
+    let reverse_k = group.scalar_invert(&k).ok_or(Error::InvalidInverse)?;
+
+    let s1 = group.scalar_mul(x, &group.element_to_scalar(&r));
+    let s = group.scalar_mul(&reverse_k, &group.scalar_sub(h, &s1));
+
+    Ok((r, s))
 }
 
+/// El Gamal signature verification in any [`Group`]: `y^r * r^s == g^h`
 #[inline]
-fn decrypt_raw(a: &BigUint, b: &BigUint, p: &BigUint, x: &BigUint) -> Result<BigUint> {
-    let mut divider: BigUint = a
-        .modpow(x, p)
-        .mod_inverse(p)
-        .ok_or(Error::InvalidPrivateKey)?
-        .to_biguint()
-        .unwrap();
-    divider *= b;
-    divider %= p;
-
-    Ok(divider)
+pub fn group_verify<G: Group>(
+    group: &G,
+    y: &G::Element,
+    h: &G::Scalar,
+    r: &G::Element,
+    s: &G::Scalar,
+) -> Result<()> {
+    let v1 = group.mul(&group.exp(y, &group.element_to_scalar(r)), &group.exp(r, s));
+    let v2 = group.exp_generator(h);
+
+    match v1 == v2 {
+        true => Ok(()),
+        false => Err(Error::Verification),
+    }
 }
 
 #[inline]
@@ -40,9 +129,7 @@ pub fn encrypt<R: RngCore + CryptoRng>(
     key: &ElgamalPublicKey,
     m: &BigUint,
 ) -> (BigUint, BigUint) {
-    let r = rng.gen_biguint_range(&BigUint::one(), key.get_q());
-
-    encrypt_raw(m, key.get_p(), key.get_y(), key.get_g(), &r)
+    group_encrypt(rng, key.group(), key.get_y(), m)
 }
 
 #[inline]
@@ -52,17 +139,64 @@ pub fn reencrypt<R: RngCore + CryptoRng>(
     a: &BigUint,
     b: &BigUint,
 ) -> (BigUint, BigUint) {
-    let r = rng.gen_biguint_range(&BigUint::one(), key.get_q());
+    group_reencrypt(rng, key.group(), key.get_y(), a, b)
+}
+
+/// Multi-recipient El Gamal Encryption (Kurosawa)
+///
+/// All keys must share the same group. A single `a = g^r` is produced together with
+/// one `b_i = m * y_i^r` per recipient, in the order of `keys`.
+#[inline]
+pub fn multi_recipient_encrypt<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    keys: &[ElgamalPublicKey],
+    m: &BigUint,
+) -> Result<(BigUint, Vec<BigUint>)> {
+    let first = keys.first().ok_or(Error::InvalidData)?;
+    if keys.iter().any(|key| key.group() != first.group()) {
+        return Err(Error::GroupMismatch);
+    }
 
-    let (mut a1, b1) = encrypt_raw(b, key.get_p(), key.get_y(), key.get_g(), &r);
-    a1 *= a;
+    let group = first.group();
+    let r = group.random_scalar(rng);
 
-    (a1, b1)
+    let a = group.exp_generator(&r);
+    let b = keys
+        .iter()
+        .map(|key| group.mul(m, &group.exp(key.get_y(), &r)))
+        .collect();
+
+    Ok((a, b))
 }
 
 #[inline]
 pub fn decrypt(key: &ElgamalPrivateKey, a: &BigUint, b: &BigUint) -> Result<BigUint> {
-    decrypt_raw(a, b, key.get_p(), key.get_x())
+    group_decrypt(key.group(), key.get_x(), a, b)
+}
+
+/// El Gamal decryption together with a Chaum–Pedersen proof that the plaintext is correct
+#[inline]
+pub fn decrypt_with_proof<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    digest: &mut dyn DynDigest,
+    key: &ElgamalPrivateKey,
+    a: &BigUint,
+    b: &BigUint,
+) -> Result<(BigUint, DecryptionProof<ElgamalGroup>)> {
+    DecryptionProof::prove(rng, key.group(), digest, key.get_x(), a, b)
+}
+
+/// Checks a proof that `m` is the decryption of `(a, b)` under `key`
+#[inline]
+pub fn verify_decryption(
+    digest: &mut dyn DynDigest,
+    key: &ElgamalPublicKey,
+    a: &BigUint,
+    b: &BigUint,
+    m: &BigUint,
+    proof: &DecryptionProof<ElgamalGroup>,
+) -> Result<()> {
+    proof.verify(key.group(), digest, key.get_y(), a, b, m)
 }
 
 #[inline]
@@ -71,14 +205,8 @@ pub fn verify(key: &ElgamalPublicKey, h: &BigUint, r: &BigUint, s: &BigUint) ->
         return Err(Error::InvalidRange);
     }
 
-    let p = key.get_p();
-    let v1 = key.get_y().modpow(r, p) * r.modpow(s, p) % p;
-    let v2 = key.get_g().modpow(h, p);
-
-    match v1 == v2 {
-        true => Ok(()),
-        false => Err(Error::Verification),
-    }
+    let group = key.group();
+    group_verify(group, key.get_y(), &group.scalar_from_biguint(h), r, s)
 }
 
 #[inline]
@@ -87,53 +215,84 @@ pub fn sign<R: RngCore + CryptoRng>(
     key: &ElgamalPrivateKey,
     h: &BigUint,
 ) -> Result<(BigUint, BigUint)> {
-    let q = key.get_q();
-    let k = rng.gen_biguint_range(&BigUint::one(), key.get_q());
-    let r = key.get_g().modpow(&k, key.get_p());
-    let reverse_k = k
-        .mod_inverse(q)
-        .ok_or(Error::InvalidInverse)?
-        .to_biguint()
-        .unwrap();
-
-    let s1 = (key.get_x() * &r) % q;
-    let s = match s1 > *h {
-        true => (reverse_k * (q + h - s1)) % q,
-        false => (reverse_k * (h - s1)) % q,
-    };
+    let group = key.group();
 
-    Ok((r, s))
+    group_sign(rng, group, key.get_x(), &group.scalar_from_biguint(h))
 }
 
-/// Non-Malleable El Gamal Encryption
+const NON_MALLEABLE_LABEL: &[u8] = b"elgamal non-malleable encryption";
+
+/// Challenge `c = H(v, a, b)` of the proof of knowledge of `r`
+fn non_malleable_challenge<G: Group>(
+    digest: &mut dyn DynDigest,
+    group: &G,
+    v: &G::Element,
+    a: &G::Element,
+    b: &G::Element,
+) -> G::Scalar {
+    let mut transcript = Transcript::new(digest, NON_MALLEABLE_LABEL);
+    transcript.append_element(group, b"v", v);
+    transcript.append_element(group, b"a", a);
+    transcript.append_element(group, b"b", b);
+
+    transcript.challenge_scalar(group, b"c")
+}
+
+/// Non-malleable El Gamal encryption in any [`Group`]: `(a, b)` with a Schnorr proof
+/// `(c, d)` of knowledge of the randomness `r`
 #[inline]
-pub fn non_malleable_encrypt<R: RngCore + CryptoRng>(
+pub fn group_non_malleable_encrypt<G: Group, R: RngCore + CryptoRng>(
     rng: &mut R,
+    group: &G,
     digest: &mut dyn DynDigest,
-    key: &ElgamalPublicKey,
-    m: &BigUint,
-) -> (BigUint, BigUint, BigUint, BigUint) {
-    let r = rng.gen_biguint_range(&BigUint::one(), key.get_q());
-    let s = rng.gen_biguint_range(&BigUint::one(), key.get_q());
+    y: &G::Element,
+    m: &G::Element,
+) -> (G::Element, G::Element, G::Scalar, G::Scalar) {
+    let r = group.random_scalar(rng);
+    let s = group.random_scalar(rng);
 
-    let g = key.get_g();
-    let p = key.get_p();
-    let q = key.get_q();
+    let (a, b) = group_encrypt_with(group, y, m, &r);
 
-    let (a, b) = encrypt_raw(m, p, key.get_y(), g, &r);
+    let v = group.exp_generator(&s);
+    let c = non_malleable_challenge(digest, group, &v, &a, &b);
+    let d = group.scalar_add(&s, &group.scalar_mul(&c, &r));
 
-    digest.reset();
+    (a, b, c, d)
+}
 
-    let v = g.modpow(&s, p);
-    digest.update(&v.to_bytes_be());
-    digest.update(&a.to_bytes_be());
-    digest.update(&b.to_bytes_be());
+/// Non-malleable El Gamal decryption in any [`Group`], rejecting ciphertexts whose
+/// proof does not verify
+#[inline]
+pub fn group_non_malleable_decrypt<G: Group>(
+    group: &G,
+    digest: &mut dyn DynDigest,
+    x: &G::Scalar,
+    a: &G::Element,
+    b: &G::Element,
+    c: &G::Scalar,
+    d: &G::Scalar,
+) -> Result<G::Element> {
+    let a_inverse = group
+        .invert(&group.exp(a, c))
+        .ok_or(Error::InvalidPrivateKey)?;
+    let v = group.mul(&group.exp_generator(d), &a_inverse);
+
+    if non_malleable_challenge(digest, group, &v, a, b) != *c {
+        return Err(Error::Verification);
+    }
 
-    let hash = digest.finalize_reset();
-    let c = BigUint::from_bytes_be(hash.as_ref()) % q;
-    let d = (&s + &c * &r) % q;
+    group_decrypt(group, x, a, b)
+}
 
-    (a, b, c, d)
+/// Non-Malleable El Gamal Encryption
+#[inline]
+pub fn non_malleable_encrypt<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    digest: &mut dyn DynDigest,
+    key: &ElgamalPublicKey,
+    m: &BigUint,
+) -> (BigUint, BigUint, BigUint, BigUint) {
+    group_non_malleable_encrypt(rng, key.group(), digest, key.get_y(), m)
 }
 
 /// Non-Malleable El Gamal Decryption
@@ -146,38 +305,14 @@ pub fn non_malleable_decrypt(
     c: &BigUint,
     d: &BigUint,
 ) -> Result<BigUint> {
-    let g = key.get_g();
-    let p = key.get_p();
-    let q = key.get_q();
-
-    let a_inverse = a
-        .modpow(c, p)
-        .mod_inverse(p)
-        .ok_or(Error::InvalidPrivateKey)?
-        .to_biguint()
-        .unwrap();
-    let v = g.modpow(d, p) * a_inverse % p;
-    digest.reset();
-    digest.update(&v.to_bytes_be());
-    digest.update(&a.to_bytes_be());
-    digest.update(&b.to_bytes_be());
-
-    let hash = digest.finalize_reset();
-    let v = BigUint::from_bytes_be(hash.as_ref()) % q;
-
-    println!("v: {}", v);
-    println!("c: {}", c);
-
-    if v != *c {
-        return Err(Error::Verification);
-    }
-
-    decrypt_raw(a, b, p, key.get_x())
+    group_non_malleable_decrypt(key.group(), digest, key.get_x(), a, b, c, d)
 }
 
 #[cfg(test)]
 mod test {
     use digest::Digest;
+    use num_bigint::RandBigInt;
+    use num_traits::One;
     use rand::{prelude::StdRng, SeedableRng};
     use sha2::Sha256;
 
@@ -185,7 +320,7 @@ mod test {
     use crate::{
         algorithms::{elgamal_parameter_generation_type1, key_generation},
         keys::{ElgamalPrivateKey, ElgamalPublicKey},
-        ElgamalGroup,
+        ElgamalGroup, Ristretto255,
     };
 
     fn generate_key<R: RngCore + CryptoRng>(
@@ -249,6 +384,48 @@ mod test {
         assert_eq!(decryprted, plain_text);
     }
 
+    #[test]
+    fn group_non_malleable_encrypt_decrypt() {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+
+        let group = Ristretto255;
+        let x = group.random_scalar(&mut rng);
+        let y = group.exp_generator(&x);
+        let m = group.exp_generator(&group.random_scalar(&mut rng));
+        let (a, b, c, d) = group_non_malleable_encrypt(&mut rng, &group, &mut digest, &y, &m);
+        assert_eq!(
+            group_non_malleable_decrypt(&group, &mut digest, &x, &a, &b, &c, &d).unwrap(),
+            m
+        );
+        let forged = group.mul(&b, &group.generator());
+        assert!(group_non_malleable_decrypt(&group, &mut digest, &x, &a, &forged, &c, &d).is_err());
+    }
+
+    #[test]
+    fn multi_recipient_encrypt_decrypt() {
+        let mut rng = StdRng::from_entropy();
+        let group = ElgamalGroup::generate(&mut rng, 70, 4);
+        let keys: Vec<_> = (0..5)
+            .map(|_| crate::keys::elgamal_key_generate(&mut rng, &group))
+            .collect();
+        let public_keys: Vec<_> = keys.iter().map(|(pub_key, _)| pub_key.clone()).collect();
+        let plain_text = rng.gen_biguint_range(&BigUint::one(), group.get_p());
+
+        let (a, b) = multi_recipient_encrypt(&mut rng, &public_keys, &plain_text).unwrap();
+        assert_eq!(b.len(), keys.len());
+
+        for ((_, priv_key), b) in keys.iter().zip(b.iter()) {
+            assert_eq!(decrypt(priv_key, &a, b).unwrap(), plain_text);
+        }
+
+        let (other_key, _) = generate_key(&mut rng, 70, 4);
+        assert!(matches!(
+            multi_recipient_encrypt(&mut rng, &[public_keys[0].clone(), other_key], &plain_text),
+            Err(Error::GroupMismatch)
+        ));
+    }
+
     #[test]
     fn sign_verify() {
         let l = 70;
@@ -1,4 +1,5 @@
 use num_bigint::BigUint;
+use num_traits::Zero;
 use rand_core::{CryptoRng, RngCore};
 
 #[cfg(feature = "serdesup")]
@@ -7,7 +8,13 @@ use serde::{Deserialize, Serialize};
 use crate::algorithms::elgamal_parameter_generation_type1;
 use crate::algorithms::key_generation;
 use crate::error::*;
+use crate::formats::{multi_recipient_decode, multi_recipient_encode, public_key_encode};
+use crate::group::Group;
 use crate::internal::*;
+use crate::proofs::SchnorrProof;
+use crate::sharing::{interpolate, Polynomial};
+
+use digest::DynDigest;
 
 pub trait ElgamalGroupElements {
     fn get_p(&self) -> &BigUint;
@@ -26,7 +33,7 @@ pub struct ElgamalGroup {
     g: BigUint,
     /// Order of cyclic group G
     p: BigUint,
-    ///
+    /// Order of the subgroup generated by `g`
     q: BigUint,
 }
 
@@ -43,6 +50,26 @@ pub struct ElgamalPublicKey {
     group: ElgamalGroup,
 }
 
+/// Shamir share of the private exponent of an [`ElgamalPrivateKey`].
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serdesup",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde")
+)]
+pub struct PrivateKeyShare {
+    /// Index of the share, starting from 1
+    pub(crate) index: u32,
+    /// Number of shares required to recover the key
+    pub(crate) threshold: u32,
+    /// x_i = f(i) mod q
+    pub(crate) share: BigUint,
+    /// y = g^x, used to check the recovered key
+    pub(crate) y: BigUint,
+    /// ElGamal Group
+    group: ElgamalGroup,
+}
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 #[cfg_attr(
     feature = "serdesup",
@@ -106,6 +133,19 @@ impl ElgamalPublicKey {
     pub fn get_y(&self) -> &BigUint {
         &self.y
     }
+
+    /// Returns the group of the key.
+    pub fn group(&self) -> &ElgamalGroup {
+        &self.group
+    }
+
+    /// Identifier of the key: hash of its DER encoding.
+    pub fn key_id(&self, digest: &mut dyn DynDigest) -> Result<Vec<u8>> {
+        digest.reset();
+        digest.update(&public_key_encode(self)?);
+
+        Ok(digest.finalize_reset().to_vec())
+    }
 }
 
 impl ElgamalPrivateKey {
@@ -122,6 +162,79 @@ impl ElgamalPrivateKey {
     pub fn public(&self) -> Option<&ElgamalPublicKey> {
         self.public.as_ref()
     }
+
+    /// Returns the group of the key.
+    pub fn group(&self) -> &ElgamalGroup {
+        &self.group
+    }
+
+    /// Returns the public key, computing `y = g^x` if it was not provided.
+    pub fn to_public_key(&self) -> ElgamalPublicKey {
+        match self.public {
+            Some(ref public) => public.clone(),
+            None => ElgamalPublicKey::new(
+                self.group.clone(),
+                self.get_g().modpow(&self.x, self.get_p()),
+            ),
+        }
+    }
+}
+
+impl PrivateKeyShare {
+    pub fn new(
+        group: ElgamalGroup,
+        index: u32,
+        threshold: u32,
+        share: BigUint,
+        y: BigUint,
+    ) -> Self {
+        Self {
+            index,
+            threshold,
+            share,
+            y,
+            group,
+        }
+    }
+
+    /// Returns the index of the share.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns the number of shares required to recover the key.
+    pub fn threshold(&self) -> u32 {
+        self.threshold
+    }
+
+    /// Returns the share of the private exponent.
+    pub fn get_share(&self) -> &BigUint {
+        &self.share
+    }
+
+    /// Returns the public exponent of the shared key.
+    pub fn get_y(&self) -> &BigUint {
+        &self.y
+    }
+
+    /// Returns the group of the key.
+    pub fn group(&self) -> &ElgamalGroup {
+        &self.group
+    }
+}
+
+impl ElgamalGroupElements for PrivateKeyShare {
+    fn get_p(&self) -> &BigUint {
+        self.group.get_p()
+    }
+
+    fn get_q(&self) -> &BigUint {
+        self.group.get_q()
+    }
+
+    fn get_g(&self) -> &BigUint {
+        self.group.get_g()
+    }
 }
 
 impl ElgamalGroupElements for ElgamalPrivateKey {
@@ -182,6 +295,19 @@ impl ElgamalPublicKey {
 
         verify(self, &h, &r, &s)
     }
+
+    /// Verify a proof of possession produced by [`ElgamalPrivateKey::prove_possession`]
+    /// with the same `context`.
+    pub fn verify_possession(
+        &self,
+        digest: &mut dyn DynDigest,
+        context: &[u8],
+        proof: &[u8],
+    ) -> Result<()> {
+        let proof = SchnorrProof::from_bytes(&self.group, proof)?;
+
+        proof.verify(&self.group, digest, context, &self.y)
+    }
 }
 
 impl ElgamalPrivateKey {
@@ -200,6 +326,74 @@ impl ElgamalPrivateKey {
         Ok(m.to_bytes_be())
     }
 
+    /// Decrypt the share of a multi-recipient ciphertext addressed to this key.
+    /// `digest` must be the hashing function used to compute the key identifiers.
+    pub fn decrypt_multi_recipient(
+        &self,
+        digest: &mut dyn DynDigest,
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>> {
+        let (a, recipients) = multi_recipient_decode(ciphertext)?;
+        // `b` carries the message, which need not lie in the subgroup
+        let p = self.get_p();
+        if !self.group.is_element(&a) || recipients.iter().any(|(_, b)| b.is_zero() || b >= p) {
+            return Err(Error::InvalidData);
+        }
+
+        let key_id = self.to_public_key().key_id(digest)?;
+
+        let (_, b) = recipients
+            .iter()
+            .find(|(id, _)| *id == key_id)
+            .ok_or(Error::UnknownRecipient)?;
+
+        let m = decrypt(self, &a, b)?;
+
+        Ok(m.to_bytes_be())
+    }
+
+    /// Proves knowledge of the private exponent, e.g. when registering the public key.
+    /// `context` should identify the registration (owner, directory) so that the
+    /// proof cannot be replayed elsewhere.
+    pub fn prove_possession<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        digest: &mut dyn DynDigest,
+        context: &[u8],
+    ) -> Vec<u8> {
+        SchnorrProof::prove(rng, &self.group, digest, context, &self.x).to_bytes(&self.group)
+    }
+
+    /// Splits the private exponent into `shares` Shamir shares, any `threshold` of
+    /// which recover the key with [`elgamal_private_key_recover`].
+    pub fn split<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        threshold: u32,
+        shares: u32,
+    ) -> Result<Vec<PrivateKeyShare>> {
+        // Indices are evaluation points modulo `q`: they must be distinct and non-zero
+        if threshold == 0 || threshold > shares || BigUint::from(shares) >= *self.get_q() {
+            return Err(Error::InvalidData);
+        }
+
+        let group = &self.group;
+        let polynomial = Polynomial::random(rng, group, self.x.clone(), threshold as usize);
+        let y = self.to_public_key().y;
+
+        Ok((1..=shares)
+            .map(|index| {
+                PrivateKeyShare::new(
+                    group.clone(),
+                    index,
+                    threshold,
+                    polynomial.evaluate(group, index),
+                    y.clone(),
+                )
+            })
+            .collect())
+    }
+
     /// Signe message.
     /// `hashed` must be the result of hashing the input using the hashing function
     /// passed in through `hash`.
@@ -230,3 +424,334 @@ pub fn elgamal_key_generate<R: RngCore + CryptoRng>(
         ElgamalPrivateKey::new(group.clone(), x, None),
     )
 }
+
+/// Encrypt the given message once for several keys sharing the same group.
+/// Every recipient is identified in the output by its key identifier computed with `digest`.
+pub fn elgamal_multi_recipient_encrypt<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    digest: &mut dyn DynDigest,
+    keys: &[ElgamalPublicKey],
+    msg: &[u8],
+) -> Result<Vec<u8>> {
+    let m = BigUint::from_bytes_be(msg);
+    let first = keys.first().ok_or(Error::InvalidData)?;
+    if m.bits() > first.get_p().bits() {
+        return Err(Error::MessageTooLong);
+    }
+
+    let (a, b) = multi_recipient_encrypt(rng, keys, &m)?;
+    let recipients = keys
+        .iter()
+        .zip(b)
+        .map(|(key, b)| Ok((key.key_id(digest)?, b)))
+        .collect::<Result<Vec<_>>>()?;
+
+    multi_recipient_encode(&a, &recipients)
+}
+
+/// Recovers a private key from at least `threshold` of its shares.
+pub fn elgamal_private_key_recover(shares: &[PrivateKeyShare]) -> Result<ElgamalPrivateKey> {
+    let first = shares.first().ok_or(Error::NotEnoughShares)?;
+    if shares
+        .iter()
+        .any(|share| share.group != first.group || share.y != first.y)
+    {
+        return Err(Error::GroupMismatch);
+    }
+    if shares
+        .iter()
+        .any(|share| share.threshold != first.threshold)
+    {
+        return Err(Error::InvalidShare);
+    }
+    if shares.len() < first.threshold as usize {
+        return Err(Error::NotEnoughShares);
+    }
+
+    let group = &first.group;
+    let points: Vec<_> = shares
+        .iter()
+        .map(|share| (share.index, share.share.clone()))
+        .collect();
+    let x = interpolate(group, &points)?;
+
+    let public = ElgamalPublicKey::new(group.clone(), first.y.clone());
+    if group.exp_generator(&x) != first.y {
+        return Err(Error::InvalidShare);
+    }
+
+    Ok(ElgamalPrivateKey::new(group.clone(), x, Some(public)))
+}
+
+/// Context bound into keys derived by [`diffie_hellman`], as in X9.42 `OtherInfo`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KdfInfo<'a> {
+    /// Algorithm and usage of the derived key
+    pub algorithm_id: &'a [u8],
+    /// Identity of the initiating party
+    pub party_u: &'a [u8],
+    /// Identity of the responding party
+    pub party_v: &'a [u8],
+}
+
+/// One-step concatenation KDF of X9.42 / SP 800-56A:
+/// `H(1 || Z || info) || H(2 || Z || info) || ...`, every info field length-prefixed.
+fn concat_kdf(digest: &mut dyn DynDigest, z: &[u8], info: &KdfInfo, size: usize) -> Vec<u8> {
+    let mut output = Vec::with_capacity(size + digest.output_size());
+    let mut counter = 1u32;
+    digest.reset();
+    while output.len() < size {
+        digest.update(&counter.to_be_bytes());
+        digest.update(z);
+        for field in [info.algorithm_id, info.party_u, info.party_v] {
+            digest.update(&(field.len() as u32).to_be_bytes());
+            digest.update(field);
+        }
+        output.extend_from_slice(&digest.finalize_reset());
+        counter += 1;
+    }
+    output.truncate(size);
+
+    output
+}
+
+/// Agrees on `size` bytes of key material with the owner of `peer`.
+///
+/// The peer key must share the group of `key` and lie in its subgroup of order `q`;
+/// the shared secret `y_peer^x`, encoded on the size of `p`, goes through
+/// the concatenation KDF with `info`.
+pub fn diffie_hellman(
+    digest: &mut dyn DynDigest,
+    key: &ElgamalPrivateKey,
+    peer: &ElgamalPublicKey,
+    info: &KdfInfo,
+    size: usize,
+) -> Result<Vec<u8>> {
+    let z = shared_secret(key, peer)?;
+
+    Ok(concat_kdf(digest, &z, info, size))
+}
+
+/// Validated `y_peer^x` in the fixed-width encoding of the group.
+pub(crate) fn shared_secret(key: &ElgamalPrivateKey, peer: &ElgamalPublicKey) -> Result<Vec<u8>> {
+    let group = key.group();
+    if peer.group() != group {
+        return Err(Error::GroupMismatch);
+    }
+    if !group.is_element(peer.get_y()) || *peer.get_y() == group.identity() {
+        return Err(Error::PublicKeyMalformed);
+    }
+
+    Ok(group.element_to_bytes(&group.exp(peer.get_y(), key.get_x())))
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{prelude::StdRng, SeedableRng};
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+
+    #[test]
+    fn multi_recipient_roundtrip() {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+        let group = ElgamalGroup::generate(&mut rng, 256, 64);
+        let keys: Vec<_> = (0..3)
+            .map(|_| elgamal_key_generate(&mut rng, &group))
+            .collect();
+        let public_keys: Vec<_> = keys.iter().map(|(pub_key, _)| pub_key.clone()).collect();
+        let msg = b"team secret";
+
+        let ciphertext =
+            elgamal_multi_recipient_encrypt(&mut rng, &mut digest, &public_keys, msg).unwrap();
+
+        for (_, priv_key) in keys.iter() {
+            let plain_text = priv_key
+                .decrypt_multi_recipient(&mut digest, &ciphertext)
+                .unwrap();
+            assert_eq!(plain_text, msg);
+        }
+
+        let (_, outsider) = elgamal_key_generate(&mut rng, &group);
+        assert!(matches!(
+            outsider.decrypt_multi_recipient(&mut digest, &ciphertext),
+            Err(Error::UnknownRecipient)
+        ));
+    }
+
+    #[test]
+    fn multi_recipient_malformed() {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+        let group = ElgamalGroup::generate(&mut rng, 256, 64);
+        let (pub_key, priv_key) = elgamal_key_generate(&mut rng, &group);
+        let (other, _) = elgamal_key_generate(&mut rng, &group);
+        let key_id = pub_key.key_id(&mut digest).unwrap();
+        let other_id = other.key_id(&mut digest).unwrap();
+
+        let ciphertext = elgamal_multi_recipient_encrypt(
+            &mut rng,
+            &mut digest,
+            &[pub_key.clone(), other],
+            b"secret",
+        )
+        .unwrap();
+        let (a, recipients) = multi_recipient_decode(&ciphertext).unwrap();
+        let b = recipients[0].1.clone();
+        let p = group.get_p();
+
+        let decrypt = |a: &BigUint, recipients: &[(Vec<u8>, BigUint)]| {
+            let ciphertext = multi_recipient_encode(a, recipients).unwrap();
+            priv_key.decrypt_multi_recipient(&mut Sha256::new(), &ciphertext)
+        };
+        assert_eq!(decrypt(&a, &recipients).unwrap(), b"secret");
+
+        // `a` outside the subgroup, `b` not a unit modulo `p`
+        let invalid = [
+            (p - 1u32, b.clone()),
+            (p.clone(), b.clone()),
+            (a.clone(), BigUint::zero()),
+            (a.clone(), p.clone()),
+        ];
+        for (a, b) in invalid {
+            assert!(matches!(
+                decrypt(&a, &[(key_id.clone(), b), recipients[1].clone()]),
+                Err(Error::InvalidData)
+            ));
+        }
+        // The share of another recipient is checked too
+        assert!(matches!(
+            decrypt(&a, &[recipients[0].clone(), (other_id, p.clone())]),
+            Err(Error::InvalidData)
+        ));
+
+        // Duplicate recipients
+        let duplicate = multi_recipient_encode(&a, &[(key_id.clone(), b.clone()), (key_id, b)]);
+        assert!(matches!(
+            multi_recipient_decode(duplicate.unwrap()),
+            Err(Error::InvalidData)
+        ));
+    }
+
+    #[test]
+    fn split_recover() {
+        let mut rng = StdRng::from_entropy();
+        let group = ElgamalGroup::generate(&mut rng, 256, 128);
+        let (_, priv_key) = elgamal_key_generate(&mut rng, &group);
+
+        let shares = priv_key.split(&mut rng, 3, 5).unwrap();
+        let recovered = elgamal_private_key_recover(&shares[2..]).unwrap();
+        assert_eq!(recovered.get_x(), priv_key.get_x());
+
+        let recovered = elgamal_private_key_recover(&[
+            shares[4].clone(),
+            shares[0].clone(),
+            shares[2].clone(),
+            shares[1].clone(),
+        ])
+        .unwrap();
+        assert_eq!(recovered.get_x(), priv_key.get_x());
+
+        assert!(matches!(
+            elgamal_private_key_recover(&shares[..2]),
+            Err(Error::NotEnoughShares)
+        ));
+
+        let mut corrupted = shares[..3].to_vec();
+        corrupted[1].share += 1u32;
+        assert!(matches!(
+            elgamal_private_key_recover(&corrupted),
+            Err(Error::InvalidShare)
+        ));
+    }
+
+    #[test]
+    fn proof_of_possession() {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+        let group = ElgamalGroup::generate(&mut rng, 256, 128);
+        let (pub_key, priv_key) = elgamal_key_generate(&mut rng, &group);
+        let (other, _) = elgamal_key_generate(&mut rng, &group);
+
+        let proof = priv_key.prove_possession(&mut rng, &mut digest, b"alice@directory");
+        pub_key
+            .verify_possession(&mut digest, b"alice@directory", &proof)
+            .unwrap();
+
+        assert!(pub_key
+            .verify_possession(&mut digest, b"bob@directory", &proof)
+            .is_err());
+        assert!(other
+            .verify_possession(&mut digest, b"alice@directory", &proof)
+            .is_err());
+
+        assert!(matches!(
+            pub_key.verify_possession(&mut digest, b"alice@directory", &proof[1..]),
+            Err(Error::InvalidData)
+        ));
+        let mut garbled = proof.clone();
+        let last = garbled.len() - 1;
+        garbled[last] ^= 1;
+        assert!(pub_key
+            .verify_possession(&mut digest, b"alice@directory", &garbled)
+            .is_err());
+    }
+
+    #[test]
+    fn split_small_order() {
+        let mut rng = StdRng::from_entropy();
+        let group = ElgamalGroup::new(23u32.into(), 11u32.into(), 4u32.into());
+        let priv_key = ElgamalPrivateKey::new(group, 7u32.into(), None);
+
+        let shares = priv_key.split(&mut rng, 2, 10).unwrap();
+        let recovered = elgamal_private_key_recover(&shares[8..]).unwrap();
+        assert_eq!(recovered.get_x(), priv_key.get_x());
+
+        // Share 11 would be f(0) = x itself.
+        assert!(matches!(
+            priv_key.split(&mut rng, 2, 11),
+            Err(Error::InvalidData)
+        ));
+    }
+
+    #[test]
+    fn key_agreement() {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+        let group = ElgamalGroup::generate(&mut rng, 256, 128);
+        let (alice_pub, alice) = elgamal_key_generate(&mut rng, &group);
+        let (bob_pub, bob) = elgamal_key_generate(&mut rng, &group);
+        let info = KdfInfo {
+            algorithm_id: b"aes-256-gcm",
+            party_u: b"alice",
+            party_v: b"bob",
+        };
+
+        let k1 = diffie_hellman(&mut digest, &alice, &bob_pub, &info, 48).unwrap();
+        let k2 = diffie_hellman(&mut digest, &bob, &alice_pub, &info, 48).unwrap();
+        assert_eq!(k1.len(), 48);
+        assert_eq!(k1, k2);
+        let other = KdfInfo {
+            party_u: b"bob",
+            party_v: b"alice",
+            ..info
+        };
+        assert_ne!(
+            k1,
+            diffie_hellman(&mut digest, &alice, &bob_pub, &other, 48).unwrap()
+        );
+
+        let identity = ElgamalPublicKey::new(group.clone(), group.identity());
+        assert!(matches!(
+            diffie_hellman(&mut digest, &alice, &identity, &info, 32),
+            Err(Error::PublicKeyMalformed)
+        ));
+        let other_group = ElgamalGroup::generate(&mut rng, 256, 128);
+        let (foreign, _) = elgamal_key_generate(&mut rng, &other_group);
+        assert!(matches!(
+            diffie_hellman(&mut digest, &alice, &foreign, &info, 32),
+            Err(Error::GroupMismatch)
+        ));
+    }
+}
@@ -1,13 +1,94 @@
 mod algorithms;
+mod ballot;
+mod bd;
+mod dkg;
+mod ec;
+#[cfg(feature = "electionguard")]
+mod electionguard;
 mod error;
 mod formats;
+mod group;
+#[cfg(feature = "helios")]
+mod helios;
+#[cfg(feature = "hpke")]
+mod hpke;
 mod internal;
 mod keys;
+#[cfg(feature = "noise")]
+mod noise;
+mod ot;
+mod pet;
+mod proofs;
+mod psi;
+mod range;
+mod sharing;
+mod shuffle;
+mod sigma;
+mod srp;
+mod tally;
+mod threshold;
+mod transcript;
 
+pub use algorithms::group_key_generation;
+pub use ballot::{BallotProof, DisjunctiveProof};
+pub use bd::{BdMessage, BdParticipant, BdPhase, BdSignature};
+pub use curve25519_dalek;
+pub use dkg::{DkgMessage, DkgParticipant, DkgPhase};
+pub use ec::{
+    ec_decrypt, ec_elgamal_key_generate, ec_encrypt, ec_group_order, ec_private_key_decode,
+    ec_private_key_encode, ec_public_key_decode, ec_public_key_encode, ec_reencrypt, ec_sign,
+    ec_verify, EcElgamalPrivateKey, EcElgamalPublicKey, Ristretto255, EC_ELEMENT_SIZE,
+};
+#[cfg(feature = "electionguard")]
+pub use electionguard::{
+    electionguard_decrypt, electionguard_encrypt, electionguard_group, electionguard_hash,
+    electionguard_parameter_hash, ElectionGuardCoefficientProof, ElectionGuardDecryptionProof,
+    ElectionGuardInput, ElectionGuardRangeProof,
+};
+pub use error::{Error, Result};
 pub use formats::{
-    private_key_decode, private_key_encode, public_key_decode, public_key_encode, GroupParams,
-    KeyInfo, PrivateKeyInfo, PublicKeyInfo,
+    multi_recipient_decode, multi_recipient_encode, private_key_decode, private_key_encode,
+    private_key_share_decode, private_key_share_encode, public_key_decode, public_key_encode,
+    GroupParams, KeyInfo, MultiRecipientInfo, PrivateKeyInfo, PrivateKeyShareInfo, PublicKeyInfo,
+    RecipientInfo,
+};
+pub use group::Group;
+#[cfg(feature = "helios")]
+pub use helios::{
+    helios_decryption_factor, helios_prove_disjunctive, helios_public_key_decode,
+    helios_public_key_encode, helios_verify_answer, helios_verify_decryption_factor,
+    helios_verify_decryption_factors, helios_verify_disjunctive, HeliosCiphertext,
+    HeliosCommitment, HeliosDecryptionFactors, HeliosEncryptedAnswer, HeliosProof, HeliosPublicKey,
+};
+#[cfg(feature = "hpke")]
+pub use hpke::{hpke_setup_receiver, hpke_setup_sender, HpkeContext, HpkeMode, HpkePsk};
+pub use internal::{
+    decrypt, decrypt_with_proof, encrypt, group_ciphertext_mul, group_decrypt, group_encrypt,
+    group_encrypt_exponent_with, group_non_malleable_decrypt, group_non_malleable_encrypt,
+    group_reencrypt, group_sign, group_verify, multi_recipient_encrypt, non_malleable_decrypt,
+    non_malleable_encrypt, reencrypt, sign, verify, verify_decryption,
 };
 pub use keys::{
-    elgamal_key_generate, ElgamalGroup, ElgamalGroupElements, ElgamalPrivateKey, ElgamalPublicKey,
+    diffie_hellman, elgamal_key_generate, elgamal_multi_recipient_encrypt,
+    elgamal_private_key_recover, ElgamalGroup, ElgamalGroupElements, ElgamalPrivateKey,
+    ElgamalPublicKey, KdfInfo, PrivateKeyShare,
+};
+#[cfg(feature = "noise")]
+pub use noise::{NoiseHandshake, NoisePattern, NoiseTransport};
+pub use ot::{
+    OtExtensionMessage, OtExtensionReceiver, OtExtensionSender, OtReceiver, OtReceiverMessage,
+    OtSender, OtSenderMessage, OtSenderSetup, OT_EXTENSION_BASE,
+};
+pub use pet::{pet_blind, pet_decide, pet_quotient, BlindingShare};
+pub use proofs::{DecryptionProof, DleqProof, SchnorrProof};
+pub use psi::{PsiClient, PsiRequest, PsiResponse, PsiServer};
+pub use range::RangeProof;
+pub use shuffle::{group_shuffle, shuffle, verify_shuffle, ShuffleProof};
+pub use sigma::{Equation, ProverState, Relation, Response, SigmaProof, Statement, Witness};
+pub use srp::{
+    srp_group, srp_verifier, SrpClient, SrpClientHello, SrpClientProof, SrpServer, SrpServerHello,
+    SrpServerProof,
 };
+pub use tally::{solve_dlog, Tally, MAX_DLOG_BOUND};
+pub use threshold::{DecryptionShare, ThresholdKeyShare, ThresholdPublicKey};
+pub use transcript::Transcript;
@@ -0,0 +1,544 @@
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+
+use crate::error::{Error, Result};
+use crate::group::Group;
+use crate::keys::{
+    elgamal_key_generate, shared_secret, ElgamalGroup, ElgamalGroupElements, ElgamalPrivateKey,
+    ElgamalPublicKey,
+};
+
+/// Size of the hash output and of the cipher keys.
+const HASHLEN: usize = 32;
+/// Size of the authentication tag of ChaChaPoly.
+const TAGLEN: usize = 16;
+/// Maximum size of a Noise message.
+const MAX_MESSAGE: usize = 65535;
+
+/// Handshake patterns of the Noise framework.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoisePattern {
+    /// Static keys transmitted during the handshake, mutual authentication
+    XX,
+    /// Static key of the responder known in advance, one round trip
+    IK,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    E,
+    S,
+    EE,
+    ES,
+    SE,
+    SS,
+}
+
+impl NoisePattern {
+    fn name(&self) -> &'static str {
+        match self {
+            NoisePattern::XX => "XX",
+            NoisePattern::IK => "IK",
+        }
+    }
+
+    fn messages(&self) -> &'static [&'static [Token]] {
+        use Token::*;
+        match self {
+            NoisePattern::XX => &[&[E], &[E, EE, S, ES], &[S, SE]],
+            NoisePattern::IK => &[&[E, ES, S, SS], &[E, EE, SE]],
+        }
+    }
+}
+
+/// `k` and nonce `n` of the Noise `CipherState`, ChaChaPoly.
+#[derive(Clone)]
+struct CipherState {
+    k: Option<[u8; HASHLEN]>,
+    n: u64,
+}
+
+impl CipherState {
+    fn new(k: Option<[u8; HASHLEN]>) -> Self {
+        Self { k, n: 0 }
+    }
+
+    /// 32 bits of zeros followed by the little-endian nonce.
+    fn nonce(&self) -> Result<[u8; 12]> {
+        // 2^64 - 1 is reserved.
+        if self.n == u64::MAX {
+            return Err(Error::InvalidState);
+        }
+        let mut nonce = [0u8; 12];
+        nonce[4..].copy_from_slice(&self.n.to_le_bytes());
+
+        Ok(nonce)
+    }
+
+    fn encrypt_with_ad(&mut self, ad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+        let k = match self.k {
+            Some(ref k) => k,
+            None => return Ok(plaintext.to_vec()),
+        };
+        let ciphertext = ChaCha20Poly1305::new(Key::from_slice(k))
+            .encrypt(
+                Nonce::from_slice(&self.nonce()?),
+                Payload {
+                    msg: plaintext,
+                    aad: ad,
+                },
+            )
+            .map_err(|_| Error::MessageTooLong)?;
+        self.n += 1;
+
+        Ok(ciphertext)
+    }
+
+    fn decrypt_with_ad(&mut self, ad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        let k = match self.k {
+            Some(ref k) => k,
+            None => return Ok(ciphertext.to_vec()),
+        };
+        let plaintext = ChaCha20Poly1305::new(Key::from_slice(k))
+            .decrypt(
+                Nonce::from_slice(&self.nonce()?),
+                Payload {
+                    msg: ciphertext,
+                    aad: ad,
+                },
+            )
+            .map_err(|_| Error::Verification)?;
+        self.n += 1;
+
+        Ok(plaintext)
+    }
+}
+
+/// `HKDF(ck, ikm)` of the Noise specification, two outputs.
+fn hkdf(ck: &[u8], ikm: &[u8]) -> ([u8; HASHLEN], [u8; HASHLEN]) {
+    let mut okm = [0u8; 2 * HASHLEN];
+    Hkdf::<Sha256>::new(Some(ck), ikm)
+        .expand(&[], &mut okm)
+        .expect("two hash outputs are a valid length");
+
+    let mut first = [0u8; HASHLEN];
+    let mut second = [0u8; HASHLEN];
+    first.copy_from_slice(&okm[..HASHLEN]);
+    second.copy_from_slice(&okm[HASHLEN..]);
+
+    (first, second)
+}
+
+/// Chaining key `ck`, handshake hash `h` and current cipher of the handshake.
+#[derive(Clone)]
+struct SymmetricState {
+    cipher: CipherState,
+    ck: [u8; HASHLEN],
+    h: [u8; HASHLEN],
+}
+
+impl SymmetricState {
+    fn new(protocol_name: &[u8]) -> Self {
+        let mut h = [0u8; HASHLEN];
+        match protocol_name.len() <= HASHLEN {
+            true => h[..protocol_name.len()].copy_from_slice(protocol_name),
+            false => h.copy_from_slice(&Sha256::digest(protocol_name)),
+        }
+
+        Self {
+            cipher: CipherState::new(None),
+            ck: h,
+            h,
+        }
+    }
+
+    fn mix_key(&mut self, ikm: &[u8]) {
+        let (ck, k) = hkdf(&self.ck, ikm);
+        self.ck = ck;
+        self.cipher = CipherState::new(Some(k));
+    }
+
+    fn mix_hash(&mut self, data: &[u8]) {
+        let mut hasher = Sha256::new();
+        hasher.update(self.h);
+        hasher.update(data);
+        self.h.copy_from_slice(&hasher.finalize());
+    }
+
+    fn encrypt_and_hash(&mut self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let ciphertext = self.cipher.encrypt_with_ad(&self.h, plaintext)?;
+        self.mix_hash(&ciphertext);
+
+        Ok(ciphertext)
+    }
+
+    fn decrypt_and_hash(&mut self, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        let plaintext = self.cipher.decrypt_with_ad(&self.h, ciphertext)?;
+        self.mix_hash(ciphertext);
+
+        Ok(plaintext)
+    }
+
+    fn split(&self) -> (CipherState, CipherState) {
+        let (k1, k2) = hkdf(&self.ck, &[]);
+        (CipherState::new(Some(k1)), CipherState::new(Some(k2)))
+    }
+}
+
+/// Noise handshake whose DH function is El Gamal key agreement in the group of the
+/// static key, with ChaChaPoly and SHA-256.
+///
+/// The protocol name is `Noise_<pattern>_FFDH<bits>_ChaChaPoly_SHA256`, `bits`
+/// being the size of `p`. Public keys are encoded on the size of `p`.
+#[derive(Clone)]
+pub struct NoiseHandshake {
+    pattern: NoisePattern,
+    initiator: bool,
+    group: ElgamalGroup,
+    state: SymmetricState,
+    s: ElgamalPrivateKey,
+    e: Option<ElgamalPrivateKey>,
+    rs: Option<ElgamalPublicKey>,
+    re: Option<ElgamalPublicKey>,
+    /// Index of the next message pattern
+    message: usize,
+}
+
+impl core::fmt::Debug for NoiseHandshake {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("NoiseHandshake")
+            .field("pattern", &self.pattern)
+            .field("initiator", &self.initiator)
+            .field("message", &self.message)
+            .finish()
+    }
+}
+
+/// Cipher states of an established Noise session.
+#[derive(Clone)]
+pub struct NoiseTransport {
+    send: CipherState,
+    receive: CipherState,
+    handshake_hash: [u8; HASHLEN],
+    remote_static: ElgamalPublicKey,
+}
+
+impl core::fmt::Debug for NoiseTransport {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("NoiseTransport")
+            .field("remote_static", &self.remote_static)
+            .finish()
+    }
+}
+
+fn encode(key: &ElgamalPublicKey) -> Vec<u8> {
+    key.group().element_to_bytes(key.get_y())
+}
+
+impl NoiseHandshake {
+    /// Starts a handshake with the static key `s`. `rs` is the static key of the
+    /// responder, required for an `IK` initiator and ignored otherwise.
+    pub fn new(
+        pattern: NoisePattern,
+        initiator: bool,
+        prologue: &[u8],
+        s: ElgamalPrivateKey,
+        rs: Option<ElgamalPublicKey>,
+    ) -> Result<Self> {
+        let group = s.group().clone();
+        let name = format!(
+            "Noise_{}_FFDH{}_ChaChaPoly_SHA256",
+            pattern.name(),
+            group.get_p().bits()
+        );
+        let mut state = SymmetricState::new(name.as_bytes());
+        state.mix_hash(prologue);
+
+        // Pre-message of IK: the responder's static key.
+        let rs = match (pattern, initiator) {
+            (NoisePattern::IK, true) => {
+                let rs = rs.ok_or(Error::InvalidData)?;
+                if rs.group() != &group {
+                    return Err(Error::GroupMismatch);
+                }
+                state.mix_hash(&encode(&rs));
+                Some(rs)
+            }
+            (NoisePattern::IK, false) => {
+                state.mix_hash(&encode(&s.to_public_key()));
+                None
+            }
+            (NoisePattern::XX, _) => None,
+        };
+
+        Ok(Self {
+            pattern,
+            initiator,
+            group,
+            state,
+            s,
+            e: None,
+            rs,
+            re: None,
+            message: 0,
+        })
+    }
+
+    /// Whether every handshake message has been sent or received.
+    pub fn is_finished(&self) -> bool {
+        self.message == self.pattern.messages().len()
+    }
+
+    /// Returns the static key of the peer, once received.
+    pub fn remote_static(&self) -> Option<&ElgamalPublicKey> {
+        self.rs.as_ref()
+    }
+
+    /// Tokens of the next message, checking that it is ours to `write`.
+    fn next_tokens(&self, write: bool) -> Result<&'static [Token]> {
+        let tokens = self
+            .pattern
+            .messages()
+            .get(self.message)
+            .ok_or(Error::InvalidState)?;
+        // The initiator writes the even messages.
+        match (self.message % 2 == 0) == (self.initiator == write) {
+            true => Ok(tokens),
+            false => Err(Error::InvalidState),
+        }
+    }
+
+    fn dh(&mut self, token: Token) -> Result<()> {
+        let missing = || Error::InvalidState;
+        let (local, remote) = match (token, self.initiator) {
+            (Token::EE, _) => (self.e.as_ref(), self.re.as_ref()),
+            (Token::SS, _) => (Some(&self.s), self.rs.as_ref()),
+            (Token::ES, true) | (Token::SE, false) => (self.e.as_ref(), self.rs.as_ref()),
+            (Token::ES, false) | (Token::SE, true) => (Some(&self.s), self.re.as_ref()),
+            _ => unreachable!("not a DH token"),
+        };
+        let secret = shared_secret(local.ok_or_else(missing)?, remote.ok_or_else(missing)?)?;
+        self.state.mix_key(&secret);
+
+        Ok(())
+    }
+
+    /// Writes the next handshake message, carrying `payload`.
+    pub fn write_message<R: RngCore + CryptoRng>(
+        &mut self,
+        rng: &mut R,
+        payload: &[u8],
+    ) -> Result<Vec<u8>> {
+        let tokens = self.next_tokens(true)?;
+
+        let mut message = Vec::new();
+        for token in tokens {
+            match token {
+                Token::E => {
+                    let (public, e) = elgamal_key_generate(rng, &self.group);
+                    let bytes = encode(&public);
+                    self.state.mix_hash(&bytes);
+                    message.extend_from_slice(&bytes);
+                    self.e = Some(e);
+                }
+                Token::S => {
+                    let s = encode(&self.s.to_public_key());
+                    message.extend_from_slice(&self.state.encrypt_and_hash(&s)?);
+                }
+                _ => self.dh(*token)?,
+            }
+        }
+        message.extend_from_slice(&self.state.encrypt_and_hash(payload)?);
+        if message.len() > MAX_MESSAGE {
+            return Err(Error::MessageTooLong);
+        }
+        self.message += 1;
+
+        Ok(message)
+    }
+
+    /// Reads the next handshake message and returns its payload.
+    pub fn read_message(&mut self, message: &[u8]) -> Result<Vec<u8>> {
+        let tokens = self.next_tokens(false)?;
+        if message.len() > MAX_MESSAGE {
+            return Err(Error::MessageTooLong);
+        }
+
+        let dhlen = self.group.element_size();
+        let mut rest = message;
+        let mut take = |size: usize| -> Result<&[u8]> {
+            if rest.len() < size {
+                return Err(Error::InvalidData);
+            }
+            let (head, tail) = rest.split_at(size);
+            rest = tail;
+            Ok(head)
+        };
+        for token in tokens {
+            match token {
+                Token::E => {
+                    let bytes = take(dhlen)?;
+                    let y = self.group.element_from_bytes(bytes)?;
+                    self.state.mix_hash(bytes);
+                    self.re = Some(ElgamalPublicKey::new(self.group.clone(), y));
+                }
+                Token::S => {
+                    let size = match self.state.cipher.k {
+                        Some(_) => dhlen + TAGLEN,
+                        None => dhlen,
+                    };
+                    let bytes = self.state.decrypt_and_hash(take(size)?)?;
+                    let y = self.group.element_from_bytes(&bytes)?;
+                    self.rs = Some(ElgamalPublicKey::new(self.group.clone(), y));
+                }
+                _ => self.dh(*token)?,
+            }
+        }
+        let payload = self.state.decrypt_and_hash(rest)?;
+        self.message += 1;
+
+        Ok(payload)
+    }
+
+    /// Splits the final state into the transport ciphers of the session.
+    pub fn into_transport(self) -> Result<NoiseTransport> {
+        if !self.is_finished() {
+            return Err(Error::InvalidState);
+        }
+        let (c1, c2) = self.state.split();
+        let (send, receive) = match self.initiator {
+            true => (c1, c2),
+            false => (c2, c1),
+        };
+
+        Ok(NoiseTransport {
+            send,
+            receive,
+            handshake_hash: self.state.h,
+            remote_static: self.rs.ok_or(Error::InvalidState)?,
+        })
+    }
+}
+
+impl NoiseTransport {
+    /// Encrypts the next outgoing message.
+    pub fn encrypt(&mut self, payload: &[u8]) -> Result<Vec<u8>> {
+        if payload.len() + TAGLEN > MAX_MESSAGE {
+            return Err(Error::MessageTooLong);
+        }
+        self.send.encrypt_with_ad(&[], payload)
+    }
+
+    /// Decrypts the next incoming message.
+    pub fn decrypt(&mut self, message: &[u8]) -> Result<Vec<u8>> {
+        if message.len() > MAX_MESSAGE {
+            return Err(Error::MessageTooLong);
+        }
+        self.receive.decrypt_with_ad(&[], message)
+    }
+
+    /// Hash of the whole handshake, identical on both sides, for channel binding.
+    pub fn handshake_hash(&self) -> &[u8] {
+        &self.handshake_hash
+    }
+
+    /// Returns the authenticated static key of the peer.
+    pub fn remote_static(&self) -> &ElgamalPublicKey {
+        &self.remote_static
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{prelude::StdRng, SeedableRng};
+
+    use super::*;
+
+    fn handshake(
+        pattern: NoisePattern,
+        initiator: &mut NoiseHandshake,
+        responder: &mut NoiseHandshake,
+    ) -> Result<()> {
+        let mut rng = StdRng::from_entropy();
+        let (mut writer, mut reader) = (initiator, responder);
+        for i in 0..pattern.messages().len() {
+            let payload = format!("message {}", i);
+            let message = writer.write_message(&mut rng, payload.as_bytes())?;
+            assert_eq!(reader.read_message(&message)?, payload.as_bytes());
+            core::mem::swap(&mut writer, &mut reader);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn noise_handshakes() {
+        let mut rng = StdRng::from_entropy();
+        let group = ElgamalGroup::generate(&mut rng, 256, 128);
+        let (alice_pub, alice) = elgamal_key_generate(&mut rng, &group);
+        let (bob_pub, bob) = elgamal_key_generate(&mut rng, &group);
+
+        for pattern in [NoisePattern::XX, NoisePattern::IK] {
+            let mut initiator = NoiseHandshake::new(
+                pattern,
+                true,
+                b"prologue",
+                alice.clone(),
+                Some(bob_pub.clone()),
+            )
+            .unwrap();
+            let mut responder =
+                NoiseHandshake::new(pattern, false, b"prologue", bob.clone(), None).unwrap();
+            handshake(pattern, &mut initiator, &mut responder).unwrap();
+
+            let mut a = initiator.into_transport().unwrap();
+            let mut b = responder.into_transport().unwrap();
+            assert_eq!(a.handshake_hash(), b.handshake_hash());
+            assert_eq!(a.remote_static(), &bob_pub);
+            assert_eq!(b.remote_static(), &alice_pub);
+
+            for msg in [&b"hello"[..], b"world"] {
+                let ct = a.encrypt(msg).unwrap();
+                assert_eq!(b.decrypt(&ct).unwrap(), msg);
+                let ct = b.encrypt(msg).unwrap();
+                assert_eq!(a.decrypt(&ct).unwrap(), msg);
+            }
+            let mut ct = a.encrypt(b"tampered").unwrap();
+            ct[0] ^= 1;
+            assert!(b.decrypt(&ct).is_err());
+        }
+    }
+
+    #[test]
+    fn noise_failures() {
+        let mut rng = StdRng::from_entropy();
+        let group = ElgamalGroup::generate(&mut rng, 256, 128);
+        let (_, alice) = elgamal_key_generate(&mut rng, &group);
+        let (_, bob) = elgamal_key_generate(&mut rng, &group);
+        let (eve_pub, _) = elgamal_key_generate(&mut rng, &group);
+
+        // IK towards the wrong responder key
+        let mut initiator =
+            NoiseHandshake::new(NoisePattern::IK, true, b"", alice.clone(), Some(eve_pub)).unwrap();
+        let mut responder =
+            NoiseHandshake::new(NoisePattern::IK, false, b"", bob.clone(), None).unwrap();
+        assert!(handshake(NoisePattern::IK, &mut initiator, &mut responder).is_err());
+
+        // Different prologues
+        let mut initiator =
+            NoiseHandshake::new(NoisePattern::XX, true, b"a", alice.clone(), None).unwrap();
+        let mut responder = NoiseHandshake::new(NoisePattern::XX, false, b"b", bob, None).unwrap();
+        assert!(handshake(NoisePattern::XX, &mut initiator, &mut responder).is_err());
+
+        // Out of turn
+        let mut initiator = NoiseHandshake::new(NoisePattern::XX, true, b"", alice, None).unwrap();
+        assert!(matches!(
+            initiator.read_message(&[]),
+            Err(Error::InvalidState)
+        ));
+        assert!(initiator.clone().into_transport().is_err());
+    }
+}
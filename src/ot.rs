@@ -0,0 +1,451 @@
+use digest::DynDigest;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::{Error, Result};
+use crate::group::{expand_hash, Group};
+
+/// First message of a 1-out-of-n transfer: elements `C_1, ..., C_(n-1)` whose
+/// discrete logarithms the receiver does not know.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OtSenderSetup<G: Group> {
+    pub c: Vec<G::Element>,
+}
+
+/// Key `PK_0` of the receiver; the sender derives `PK_i = C_i / PK_0` and the
+/// receiver knows the logarithm of `PK_choice` only.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OtReceiverMessage<G: Group> {
+    pub pk0: G::Element,
+}
+
+/// Every message masked with a key derived from `PK_i^r`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OtSenderMessage<G: Group> {
+    /// g^r
+    pub gr: G::Element,
+    /// m_i XOR H(i, PK_i^r)
+    pub ciphertexts: Vec<Vec<u8>>,
+}
+
+/// Sender of a 1-out-of-n Naor–Pinkas oblivious transfer, semi-honest security;
+/// 1-out-of-2 is the Bellare–Micali case `n = 2`.
+#[derive(Debug, Clone)]
+pub struct OtSender<G: Group> {
+    group: G,
+    c: Vec<G::Element>,
+}
+
+/// Receiver of a 1-out-of-n oblivious transfer.
+#[derive(Debug, Clone)]
+pub struct OtReceiver<G: Group> {
+    group: G,
+    choice: usize,
+    k: G::Scalar,
+}
+
+/// Mask for message `index` under the shared element `key`.
+fn ot_pad<G: Group>(
+    group: &G,
+    digest: &mut dyn DynDigest,
+    index: usize,
+    key: &G::Element,
+    size: usize,
+) -> Vec<u8> {
+    let mut input = b"elgamal oblivious transfer".to_vec();
+    input.extend_from_slice(&(index as u32).to_be_bytes());
+    input.extend_from_slice(&group.element_to_bytes(key));
+
+    expand_hash(digest, &input, size)
+}
+
+fn xor(a: &[u8], b: &[u8]) -> Vec<u8> {
+    a.iter().zip(b).map(|(x, y)| x ^ y).collect()
+}
+
+impl<G: Group> OtSender<G> {
+    /// Starts a transfer of one out of `n >= 2` messages.
+    pub fn new<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        group: &G,
+        n: usize,
+    ) -> Result<(Self, OtSenderSetup<G>)> {
+        if n < 2 {
+            return Err(Error::InvalidData);
+        }
+
+        let c: Vec<_> = (1..n)
+            .map(|_| group.exp_generator(&group.random_scalar(rng)))
+            .collect();
+        let setup = OtSenderSetup { c: c.clone() };
+
+        Ok((
+            Self {
+                group: group.clone(),
+                c,
+            },
+            setup,
+        ))
+    }
+
+    /// Masks every message so that the receiver can open only the chosen one.
+    pub fn transfer<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        digest: &mut dyn DynDigest,
+        message: &OtReceiverMessage<G>,
+        messages: &[&[u8]],
+    ) -> Result<OtSenderMessage<G>> {
+        let group = &self.group;
+        if messages.len() != self.c.len() + 1 {
+            return Err(Error::InvalidData);
+        }
+        if !group.is_element(&message.pk0) {
+            return Err(Error::InvalidData);
+        }
+
+        let inverse = group.invert(&message.pk0).ok_or(Error::InvalidInverse)?;
+        let r = group.random_scalar(rng);
+        let ciphertexts = messages
+            .iter()
+            .enumerate()
+            .map(|(i, m)| {
+                let pk = match i {
+                    0 => message.pk0.clone(),
+                    _ => group.mul(&self.c[i - 1], &inverse),
+                };
+                let key = group.exp(&pk, &r);
+                xor(m, &ot_pad(group, digest, i, &key, m.len()))
+            })
+            .collect();
+
+        Ok(OtSenderMessage {
+            gr: group.exp_generator(&r),
+            ciphertexts,
+        })
+    }
+}
+
+impl<G: Group> OtReceiver<G> {
+    /// Chooses message `choice` out of the `n` announced by `setup`.
+    pub fn new<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        group: &G,
+        setup: &OtSenderSetup<G>,
+        choice: usize,
+    ) -> Result<(Self, OtReceiverMessage<G>)> {
+        if choice > setup.c.len() {
+            return Err(Error::InvalidRange);
+        }
+        if setup.c.iter().any(|c| !group.is_element(c)) {
+            return Err(Error::InvalidData);
+        }
+
+        let k = group.random_scalar(rng);
+        let pk = group.exp_generator(&k);
+        let pk0 = match choice {
+            0 => pk,
+            _ => group.mul(
+                &setup.c[choice - 1],
+                &group.invert(&pk).ok_or(Error::InvalidInverse)?,
+            ),
+        };
+
+        Ok((
+            Self {
+                group: group.clone(),
+                choice,
+                k,
+            },
+            OtReceiverMessage { pk0 },
+        ))
+    }
+
+    /// Opens the chosen message.
+    pub fn receive(
+        &self,
+        digest: &mut dyn DynDigest,
+        message: &OtSenderMessage<G>,
+    ) -> Result<Vec<u8>> {
+        let group = &self.group;
+        if !group.is_element(&message.gr) {
+            return Err(Error::InvalidData);
+        }
+        let ciphertext = message
+            .ciphertexts
+            .get(self.choice)
+            .ok_or(Error::InvalidData)?;
+
+        let key = group.exp(&message.gr, &self.k);
+        Ok(xor(
+            ciphertext,
+            &ot_pad(group, digest, self.choice, &key, ciphertext.len()),
+        ))
+    }
+}
+
+/// Number of base transfers, and bits of security, of the OT extension.
+pub const OT_EXTENSION_BASE: usize = 128;
+
+/// Columns `u_i = G(k_i^0) XOR G(k_i^1) XOR r` sent by the extension receiver.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OtExtensionMessage {
+    pub columns: Vec<Vec<u8>>,
+}
+
+/// Sender of the IKNP OT extension: `m` 1-out-of-2 transfers from
+/// [`OT_EXTENSION_BASE`] base transfers run in the opposite direction, semi-honest
+/// security.
+#[derive(Debug, Clone)]
+pub struct OtExtensionSender {
+    s: Vec<bool>,
+}
+
+/// Receiver of the IKNP OT extension.
+#[derive(Debug, Clone)]
+pub struct OtExtensionReceiver {
+    choices: Vec<bool>,
+    seeds: Vec<(Vec<u8>, Vec<u8>)>,
+    t: Vec<Vec<u8>>,
+}
+
+const SEED_SIZE: usize = OT_EXTENSION_BASE / 8;
+
+fn bit(bytes: &[u8], i: usize) -> bool {
+    bytes[i / 8] >> (i % 8) & 1 == 1
+}
+
+fn prg(digest: &mut dyn DynDigest, seed: &[u8], m: usize) -> Vec<u8> {
+    let mut input = b"elgamal ot extension prg".to_vec();
+    input.extend_from_slice(seed);
+
+    expand_hash(digest, &input, (m + 7) / 8)
+}
+
+/// Row `j` of a matrix given by its columns.
+fn row(columns: &[Vec<u8>], j: usize) -> Vec<u8> {
+    let mut row = vec![0u8; (columns.len() + 7) / 8];
+    for (i, column) in columns.iter().enumerate() {
+        row[i / 8] |= (bit(column, j) as u8) << (i % 8);
+    }
+
+    row
+}
+
+/// Mask of transfer `j` under the row `key`.
+fn extension_pad(digest: &mut dyn DynDigest, j: usize, key: &[u8], size: usize) -> Vec<u8> {
+    let mut input = b"elgamal ot extension".to_vec();
+    input.extend_from_slice(&(j as u64).to_be_bytes());
+    input.extend_from_slice(key);
+
+    expand_hash(digest, &input, size)
+}
+
+impl OtExtensionSender {
+    /// Draws the secret `s`, the choices of the sender as base receiver.
+    pub fn new<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        let mut bytes = [0u8; SEED_SIZE];
+        rng.fill_bytes(&mut bytes);
+
+        Self {
+            s: (0..OT_EXTENSION_BASE).map(|i| bit(&bytes, i)).collect(),
+        }
+    }
+
+    /// Choices to use in the base transfers, one per base transfer.
+    pub fn base_choices(&self) -> &[bool] {
+        &self.s
+    }
+
+    /// Masks every pair `(x_j^0, x_j^1)` given the seeds `k_i^(s_i)` obtained from
+    /// the base transfers.
+    pub fn transfer(
+        &self,
+        digest: &mut dyn DynDigest,
+        seeds: &[Vec<u8>],
+        message: &OtExtensionMessage,
+        pairs: &[(&[u8], &[u8])],
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let m = pairs.len();
+        if seeds.len() != OT_EXTENSION_BASE || message.columns.len() != OT_EXTENSION_BASE {
+            return Err(Error::InvalidData);
+        }
+        if message.columns.iter().any(|u| u.len() != (m + 7) / 8) {
+            return Err(Error::InvalidData);
+        }
+
+        // q_i = G(k_i^(s_i)) XOR s_i u_i = t_i XOR s_i r
+        let q: Vec<_> = seeds
+            .iter()
+            .zip(&message.columns)
+            .zip(&self.s)
+            .map(|((seed, u), s)| {
+                let column = prg(digest, seed, m);
+                match s {
+                    true => xor(&column, u),
+                    false => column,
+                }
+            })
+            .collect();
+        let s: Vec<_> = (0..SEED_SIZE)
+            .map(|byte| (0..8).fold(0u8, |acc, i| acc | (self.s[byte * 8 + i] as u8) << i))
+            .collect();
+
+        Ok(pairs
+            .iter()
+            .enumerate()
+            .map(|(j, (x0, x1))| {
+                let q_j = row(&q, j);
+                let y0 = xor(x0, &extension_pad(digest, j, &q_j, x0.len()));
+                let y1 = xor(x1, &extension_pad(digest, j, &xor(&q_j, &s), x1.len()));
+                (y0, y1)
+            })
+            .collect())
+    }
+}
+
+impl OtExtensionReceiver {
+    /// Draws the seed pairs of the base transfers and derives the columns sent to
+    /// the sender for the given choice bits.
+    pub fn new<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        digest: &mut dyn DynDigest,
+        choices: &[bool],
+    ) -> (Self, OtExtensionMessage) {
+        let m = choices.len();
+        let mut r = vec![0u8; (m + 7) / 8];
+        for (j, choice) in choices.iter().enumerate() {
+            r[j / 8] |= (*choice as u8) << (j % 8);
+        }
+
+        let mut seed = || {
+            let mut bytes = vec![0u8; SEED_SIZE];
+            rng.fill_bytes(&mut bytes);
+            bytes
+        };
+        let seeds: Vec<_> = (0..OT_EXTENSION_BASE).map(|_| (seed(), seed())).collect();
+
+        let mut t = Vec::with_capacity(OT_EXTENSION_BASE);
+        let mut columns = Vec::with_capacity(OT_EXTENSION_BASE);
+        for (k0, k1) in seeds.iter() {
+            let t_i = prg(digest, k0, m);
+            columns.push(xor(&xor(&t_i, &prg(digest, k1, m)), &r));
+            t.push(t_i);
+        }
+
+        (
+            Self {
+                choices: choices.to_vec(),
+                seeds,
+                t,
+            },
+            OtExtensionMessage { columns },
+        )
+    }
+
+    /// Seed pairs `(k_i^0, k_i^1)` to send in the base transfers.
+    pub fn base_messages(&self) -> &[(Vec<u8>, Vec<u8>)] {
+        &self.seeds
+    }
+
+    /// Opens the chosen message of every pair.
+    pub fn receive(
+        &self,
+        digest: &mut dyn DynDigest,
+        ciphertexts: &[(Vec<u8>, Vec<u8>)],
+    ) -> Result<Vec<Vec<u8>>> {
+        if ciphertexts.len() != self.choices.len() {
+            return Err(Error::InvalidData);
+        }
+
+        Ok(ciphertexts
+            .iter()
+            .zip(&self.choices)
+            .enumerate()
+            .map(|(j, ((y0, y1), choice))| {
+                let y = if *choice { y1 } else { y0 };
+                xor(y, &extension_pad(digest, j, &row(&self.t, j), y.len()))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{prelude::StdRng, SeedableRng};
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::{ElgamalGroup, Ristretto255};
+
+    fn transfer<G: Group>(group: &G) {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+        let messages: [&[u8]; 3] = [b"first", b"second message", b"third"];
+
+        for choice in 0..3 {
+            let (sender, setup) = OtSender::new(&mut rng, group, 3).unwrap();
+            let (receiver, request) = OtReceiver::new(&mut rng, group, &setup, choice).unwrap();
+            let response = sender
+                .transfer(&mut rng, &mut digest, &request, &messages)
+                .unwrap();
+            assert_eq!(
+                receiver.receive(&mut digest, &response).unwrap(),
+                messages[choice]
+            );
+            for (i, c) in response.ciphertexts.iter().enumerate() {
+                if i != choice {
+                    assert_ne!(c.as_slice(), messages[i]);
+                }
+            }
+        }
+
+        let (_, setup) = OtSender::new(&mut rng, group, 2).unwrap();
+        assert!(OtReceiver::new(&mut rng, group, &setup, 2).is_err());
+    }
+
+    #[test]
+    fn oblivious_transfer() {
+        let mut rng = StdRng::from_entropy();
+        transfer(&ElgamalGroup::generate(&mut rng, 256, 128));
+        transfer(&Ristretto255);
+    }
+
+    #[test]
+    fn ot_extension() {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+        let group = Ristretto255;
+
+        let choices: Vec<bool> = (0..50).map(|j| j % 3 == 0).collect();
+        let pairs: Vec<(Vec<u8>, Vec<u8>)> =
+            (0..50u8).map(|j| (vec![j; 20], vec![!j; 20])).collect();
+
+        let sender = OtExtensionSender::new(&mut rng);
+        let (receiver, message) = OtExtensionReceiver::new(&mut rng, &mut digest, &choices);
+
+        // Base transfers, roles reversed
+        let seeds: Vec<_> = receiver
+            .base_messages()
+            .iter()
+            .zip(sender.base_choices())
+            .map(|((k0, k1), s)| {
+                let (base_sender, setup) = OtSender::new(&mut rng, &group, 2).unwrap();
+                let (base_receiver, request) =
+                    OtReceiver::new(&mut rng, &group, &setup, *s as usize).unwrap();
+                let response = base_sender
+                    .transfer(&mut rng, &mut digest, &request, &[k0, k1])
+                    .unwrap();
+                base_receiver.receive(&mut digest, &response).unwrap()
+            })
+            .collect();
+
+        let refs: Vec<(&[u8], &[u8])> = pairs.iter().map(|(a, b)| (&a[..], &b[..])).collect();
+        let ciphertexts = sender
+            .transfer(&mut digest, &seeds, &message, &refs)
+            .unwrap();
+        let received = receiver.receive(&mut digest, &ciphertexts).unwrap();
+        for ((x, (x0, x1)), choice) in received.iter().zip(&pairs).zip(&choices) {
+            assert_eq!(x, if *choice { x1 } else { x0 });
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use digest::DynDigest;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::{Error, Result};
+use crate::group::Group;
+use crate::proofs::{DleqProof, SchnorrProof};
+use crate::sharing::check_indices;
+use crate::threshold::{DecryptionShare, ThresholdKeyShare, ThresholdPublicKey};
+
+/// Blinding `(a^z_i, b^z_i)` of the quotient ciphertext by a single key holder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlindingShare<G: Group> {
+    /// Index of the key holder
+    pub index: u32,
+    /// a^z_i
+    pub a: G::Element,
+    /// b^z_i
+    pub b: G::Element,
+    /// Proof that `log_a a^z_i = log_b b^z_i`, bound to `index`
+    pub proof: DleqProof<G>,
+    /// Proof of knowledge of the key share `x_i` of holder `index`, bound to the
+    /// quotient and its blinding
+    pub holder: SchnorrProof<G>,
+}
+
+const BLINDING_LABEL: &[u8] = b"elgamal pet blinding";
+
+/// Domain label of the blinding proof of holder `index`.
+fn blinding_label(index: u32) -> Vec<u8> {
+    let mut label = BLINDING_LABEL.to_vec();
+    label.extend_from_slice(&index.to_be_bytes());
+
+    label
+}
+
+/// Context of the proof of knowledge of the key share: index, quotient and blinding.
+fn holder_context<G: Group>(group: &G, index: u32, elements: [&G::Element; 4]) -> Vec<u8> {
+    let mut context = blinding_label(index);
+    for element in elements {
+        context.extend_from_slice(&group.element_to_bytes(element));
+    }
+
+    context
+}
+
+/// Quotient `(a1 / a2, b1 / b2)` of two ciphertexts, an encryption of the identity
+/// exactly when both ciphertexts encrypt the same plaintext.
+pub fn pet_quotient<G: Group>(
+    group: &G,
+    (a1, b1): (&G::Element, &G::Element),
+    (a2, b2): (&G::Element, &G::Element),
+) -> Result<(G::Element, G::Element)> {
+    if [a1, b1, a2, b2].iter().any(|e| !group.is_element(e)) {
+        return Err(Error::InvalidData);
+    }
+
+    let a2 = group.invert(a2).ok_or(Error::InvalidInverse)?;
+    let b2 = group.invert(b2).ok_or(Error::InvalidInverse)?;
+
+    Ok((group.mul(a1, &a2), group.mul(b1, &b2)))
+}
+
+impl<G: Group> BlindingShare<G> {
+    /// Raises the quotient ciphertext to a fresh random exponent, with a proof that
+    /// both components use the same one and a proof that the blinding comes from the
+    /// holder of `key`.
+    pub fn new<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        digest: &mut dyn DynDigest,
+        key: &ThresholdKeyShare<G>,
+        (a, b): (&G::Element, &G::Element),
+    ) -> Self {
+        let group = key.public().group();
+        let index = key.index();
+        let z = group.random_scalar(rng);
+        let (za, zb) = (group.exp(a, &z), group.exp(b, &z));
+
+        let label = blinding_label(index);
+        let proof = DleqProof::prove_labelled(rng, group, digest, &label, &[a, b], a, b, &z);
+        let context = holder_context(group, index, [a, b, &za, &zb]);
+        let holder = SchnorrProof::prove(rng, group, digest, &context, key.get_share());
+
+        Self {
+            index,
+            a: za,
+            b: zb,
+            proof,
+            holder,
+        }
+    }
+
+    /// Verifies the blinding of the quotient ciphertext `(a, b)` by the holder of the
+    /// share `index` of `public`.
+    pub fn verify(
+        &self,
+        public: &ThresholdPublicKey<G>,
+        digest: &mut dyn DynDigest,
+        (a, b): (&G::Element, &G::Element),
+    ) -> Result<()> {
+        let group = public.group();
+        if self.index == 0 || !group.is_element(&self.a) || !group.is_element(&self.b) {
+            return Err(Error::InvalidData);
+        }
+
+        let label = blinding_label(self.index);
+        self.proof
+            .verify_labelled(group, digest, &label, &[a, b], a, &self.a, b, &self.b)?;
+
+        let context = holder_context(group, self.index, [a, b, &self.a, &self.b]);
+        self.holder.verify(
+            group,
+            digest,
+            &context,
+            &public.verification_key(self.index),
+        )
+    }
+}
+
+/// Combines verified blinding shares of at least `threshold` distinct key holders into
+/// the blinded quotient `(a^z, b^z)`, `z = sum z_i`, which still encrypts the identity
+/// when the plaintexts are equal and a random element otherwise. Fewer holders could
+/// together know `z` and learn the ratio of the plaintexts from the decryption.
+pub fn pet_blind<G: Group>(
+    public: &ThresholdPublicKey<G>,
+    digest: &mut dyn DynDigest,
+    quotient: (&G::Element, &G::Element),
+    shares: &[BlindingShare<G>],
+) -> Result<(G::Element, G::Element)> {
+    let group = public.group();
+    if shares.len() < public.threshold() {
+        return Err(Error::NotEnoughShares);
+    }
+    let indices: Vec<u32> = shares.iter().map(|share| share.index).collect();
+    check_indices(&indices)?;
+
+    let mut blinded = (group.identity(), group.identity());
+    for share in shares {
+        share.verify(public, digest, quotient)?;
+        blinded = (
+            group.mul(&blinded.0, &share.a),
+            group.mul(&blinded.1, &share.b),
+        );
+    }
+
+    Ok(blinded)
+}
+
+/// Jointly decrypts the blinded quotient from verified decryption shares and tells
+/// whether the two original ciphertexts encrypt the same plaintext.
+pub fn pet_decide<G: Group>(
+    public: &ThresholdPublicKey<G>,
+    digest: &mut dyn DynDigest,
+    (a, b): (&G::Element, &G::Element),
+    shares: &[DecryptionShare<G>],
+) -> Result<bool> {
+    let group = public.group();
+    if *a == group.identity() {
+        return Err(Error::InvalidData);
+    }
+
+    Ok(public.combine(digest, a, b, shares)? == group.identity())
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{prelude::StdRng, SeedableRng};
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::internal::{group_encrypt, group_reencrypt};
+    use crate::sharing::Polynomial;
+    use crate::threshold::ThresholdKeyShare;
+    use crate::Ristretto255;
+
+    /// 2-of-3 key dealt by a trusted dealer.
+    fn deal(
+        rng: &mut StdRng,
+        group: &Ristretto255,
+    ) -> (
+        ThresholdPublicKey<Ristretto255>,
+        Vec<ThresholdKeyShare<Ristretto255>>,
+    ) {
+        let secret = group.random_scalar(rng);
+        let polynomial = Polynomial::random(rng, group, secret, 2);
+        let public = ThresholdPublicKey::new(*group, polynomial.commitments(group)).unwrap();
+        let shares = (1..=3)
+            .map(|i| {
+                ThresholdKeyShare::new(i, polynomial.evaluate(group, i), public.clone()).unwrap()
+            })
+            .collect();
+
+        (public, shares)
+    }
+
+    #[test]
+    fn plaintext_equivalence() {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+        let group = Ristretto255;
+        let (public, shares) = deal(&mut rng, &group);
+        let y = public.get_y();
+
+        let m = group.exp_generator(&group.scalar_from_u64(42));
+        let (a1, b1) = group_encrypt(&mut rng, &group, y, &m);
+        let (a2, b2) = group_reencrypt(&mut rng, &group, y, &a1, &b1);
+        let (a3, b3) = group_encrypt(&mut rng, &group, y, &group.generator());
+
+        let mut test = |other: (&_, &_)| {
+            let quotient = pet_quotient(&group, (&a1, &b1), other).unwrap();
+            let (a, b) = (&quotient.0, &quotient.1);
+            let blindings: Vec<_> = shares
+                .iter()
+                .map(|share| BlindingShare::new(&mut rng, &mut digest, share, (a, b)))
+                .collect();
+            let (a, b) = pet_blind(&public, &mut digest, (a, b), &blindings).unwrap();
+
+            let decryptions: Vec<_> = shares[1..]
+                .iter()
+                .map(|share| share.decryption_share(&mut rng, &mut digest, &a).unwrap())
+                .collect();
+            pet_decide(&public, &mut digest, (&a, &b), &decryptions).unwrap()
+        };
+
+        assert!(test((&a2, &b2)));
+        assert!(!test((&a3, &b3)));
+    }
+
+    #[test]
+    fn forged_blinding() {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+        let group = Ristretto255;
+        let (public, shares) = deal(&mut rng, &group);
+
+        let (a, b) = group_encrypt(&mut rng, &group, public.get_y(), &group.generator());
+        let blind = |rng: &mut StdRng, digest: &mut Sha256, share| {
+            BlindingShare::new(rng, digest, share, (&a, &b))
+        };
+        let honest: Vec<_> = shares
+            .iter()
+            .map(|share| blind(&mut rng, &mut digest, share))
+            .collect();
+        pet_blind(&public, &mut digest, (&a, &b), &honest).unwrap();
+
+        // Components with different exponents.
+        let mut forged = honest.clone();
+        forged[0].b = group.identity();
+        assert!(pet_blind(&public, &mut digest, (&a, &b), &forged).is_err());
+
+        // A single holder knows the whole exponent.
+        assert!(matches!(
+            pet_blind(&public, &mut digest, (&a, &b), &honest[..1]),
+            Err(Error::NotEnoughShares)
+        ));
+
+        // Holder 1 relabels its own blinding, or another one, to pass as holder 3.
+        let own = blind(&mut rng, &mut digest, &shares[0]);
+        let mut relabelled = own.clone();
+        relabelled.index = 3;
+        assert!(pet_blind(&public, &mut digest, (&a, &b), &[own.clone(), relabelled]).is_err());
+        let mut stolen = honest[1].clone();
+        stolen.index = 3;
+        assert!(pet_blind(&public, &mut digest, (&a, &b), &[own.clone(), stolen]).is_err());
+
+        // Repeated holder.
+        assert!(pet_blind(&public, &mut digest, (&a, &b), &[own.clone(), own]).is_err());
+    }
+}
@@ -0,0 +1,470 @@
+use digest::DynDigest;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::{Error, Result};
+use crate::group::Group;
+use crate::internal::group_decrypt;
+use crate::sigma::{Relation, Response, SigmaProof, Statement, Witness};
+use crate::transcript::Transcript;
+
+/// Fiat–Shamir challenge over a [`Transcript`] labelled with the proof domain.
+pub(crate) fn challenge<G: Group>(
+    group: &G,
+    digest: &mut dyn DynDigest,
+    label: &[u8],
+    elements: &[&G::Element],
+) -> G::Scalar {
+    let mut transcript = Transcript::new(digest, label);
+    for element in elements {
+        transcript.append_element(group, b"element", element);
+    }
+
+    transcript.challenge_scalar(group, b"challenge")
+}
+
+/// Label of a proof bound to additional `context` elements.
+fn context_label<G: Group>(group: &G, label: &[u8], context: &[&G::Element]) -> Vec<u8> {
+    let mut label = label.to_vec();
+    label.extend_from_slice(&(context.len() as u64).to_be_bytes());
+    for element in context {
+        label.extend_from_slice(&group.element_to_bytes(element));
+    }
+
+    label
+}
+
+/// Single-witness sigma proof of `relation` with `x`.
+fn prove_relation<G: Group, R: RngCore + CryptoRng>(
+    rng: &mut R,
+    group: &G,
+    digest: &mut dyn DynDigest,
+    label: &[u8],
+    relation: Relation<G>,
+    x: &G::Scalar,
+) -> (G::Scalar, G::Scalar) {
+    let statement = Statement::Relation(relation);
+    let witness = Witness::Relation(vec![x.clone()]);
+    let proof = SigmaProof::prove(rng, group, digest, label, &statement, &witness)
+        .expect("the witness matches the relation");
+
+    match proof.response {
+        Response::Relation(mut s) if s.len() == 1 => (proof.challenge, s.remove(0)),
+        _ => unreachable!("single-witness relation"),
+    }
+}
+
+/// Verifies a proof produced by [`prove_relation`].
+fn verify_relation<G: Group>(
+    group: &G,
+    digest: &mut dyn DynDigest,
+    label: &[u8],
+    relation: Relation<G>,
+    c: &G::Scalar,
+    r: &G::Scalar,
+) -> Result<()> {
+    let proof = SigmaProof {
+        challenge: c.clone(),
+        response: Response::Relation(vec![r.clone()]),
+    };
+
+    proof.verify(group, digest, label, &Statement::Relation(relation))
+}
+
+/// Chaum–Pedersen proof that `log_g1 h1 = log_g2 h2`, a [`SigmaProof`] of
+/// [`Relation::dleq`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DleqProof<G: Group> {
+    /// Fiat–Shamir challenge
+    pub c: G::Scalar,
+    /// Response `w + c * x`
+    pub r: G::Scalar,
+}
+
+const DLEQ_LABEL: &[u8] = b"elgamal dleq proof";
+
+impl<G: Group> DleqProof<G> {
+    /// Proves knowledge of `x` such that `h1 = g1^x` and `h2 = g2^x`.
+    pub fn prove<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        group: &G,
+        digest: &mut dyn DynDigest,
+        g1: &G::Element,
+        g2: &G::Element,
+        x: &G::Scalar,
+    ) -> Self {
+        Self::prove_labelled(rng, group, digest, DLEQ_LABEL, &[], g1, g2, x)
+    }
+
+    /// Verifies the proof for `h1 = g1^x` and `h2 = g2^x`.
+    pub fn verify(
+        &self,
+        group: &G,
+        digest: &mut dyn DynDigest,
+        g1: &G::Element,
+        h1: &G::Element,
+        g2: &G::Element,
+        h2: &G::Element,
+    ) -> Result<()> {
+        self.verify_labelled(group, digest, DLEQ_LABEL, &[], g1, h1, g2, h2)
+    }
+
+    /// Proof bound to a domain `label` and to additional `context` elements.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn prove_labelled<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        group: &G,
+        digest: &mut dyn DynDigest,
+        label: &[u8],
+        context: &[&G::Element],
+        g1: &G::Element,
+        g2: &G::Element,
+        x: &G::Scalar,
+    ) -> Self {
+        let h1 = group.exp(g1, x);
+        let h2 = group.exp(g2, x);
+        let relation = Relation::dleq(g1.clone(), h1, g2.clone(), h2);
+        let label = context_label(group, label, context);
+        let (c, r) = prove_relation(rng, group, digest, &label, relation, x);
+
+        Self { c, r }
+    }
+
+    /// Verifies a proof produced by [`DleqProof::prove_labelled`].
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn verify_labelled(
+        &self,
+        group: &G,
+        digest: &mut dyn DynDigest,
+        label: &[u8],
+        context: &[&G::Element],
+        g1: &G::Element,
+        h1: &G::Element,
+        g2: &G::Element,
+        h2: &G::Element,
+    ) -> Result<()> {
+        let relation = Relation::dleq(g1.clone(), h1.clone(), g2.clone(), h2.clone());
+        let label = context_label(group, label, context);
+
+        verify_relation(group, digest, &label, relation, &self.c, &self.r)
+    }
+
+    /// Recomputes `t = g^r / h^c`.
+    pub(crate) fn commitment(
+        group: &G,
+        g: &G::Element,
+        h: &G::Element,
+        c: &G::Scalar,
+        r: &G::Scalar,
+    ) -> Result<G::Element> {
+        let hc = group
+            .invert(&group.exp(h, c))
+            .ok_or(Error::InvalidInverse)?;
+
+        Ok(group.mul(&group.exp(g, r), &hc))
+    }
+
+    /// Encodes the proof as `c || r`.
+    pub fn to_bytes(&self, group: &G) -> Vec<u8> {
+        let mut data = group.scalar_to_bytes(&self.c);
+        data.extend_from_slice(&group.scalar_to_bytes(&self.r));
+
+        data
+    }
+
+    /// Decodes a proof produced by [`DleqProof::to_bytes`].
+    pub fn from_bytes(group: &G, data: &[u8]) -> Result<Self> {
+        if data.len() != 2 * group.scalar_size() {
+            return Err(Error::InvalidData);
+        }
+
+        let (c, r) = data.split_at(group.scalar_size());
+        Ok(Self {
+            c: group.scalar_from_bytes(c)?,
+            r: group.scalar_from_bytes(r)?,
+        })
+    }
+}
+
+/// Proof that `m` is the decryption of `(a, b)` under the public key `y`,
+/// i.e. that `log_g y = log_a (b / m)`. It can be checked without the private key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecryptionProof<G: Group>(pub DleqProof<G>);
+
+const DECRYPTION_LABEL: &[u8] = b"elgamal decryption proof";
+
+impl<G: Group> DecryptionProof<G> {
+    /// Decrypts `(a, b)` with the private key `x` and proves the result correct.
+    pub fn prove<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        group: &G,
+        digest: &mut dyn DynDigest,
+        x: &G::Scalar,
+        a: &G::Element,
+        b: &G::Element,
+    ) -> Result<(G::Element, Self)> {
+        if !group.is_element(a) {
+            return Err(Error::InvalidData);
+        }
+
+        // `b` and `m` may lie outside the subgroup, as for plain encryptions of
+        // small integers; `b / m = a^x` is in it since `a` is
+        let m = group_decrypt(group, x, a, b)?;
+        let proof = DleqProof::prove_labelled(
+            rng,
+            group,
+            digest,
+            DECRYPTION_LABEL,
+            &[b, &m],
+            &group.generator(),
+            a,
+            x,
+        );
+
+        Ok((m, Self(proof)))
+    }
+
+    /// Verifies that `m` is the decryption of `(a, b)` under `y`.
+    pub fn verify(
+        &self,
+        group: &G,
+        digest: &mut dyn DynDigest,
+        y: &G::Element,
+        a: &G::Element,
+        b: &G::Element,
+        m: &G::Element,
+    ) -> Result<()> {
+        let ax = group.mul(b, &group.invert(m).ok_or(Error::InvalidInverse)?);
+        if [y, a, &ax].iter().any(|element| !group.is_element(element)) {
+            return Err(Error::InvalidData);
+        }
+
+        self.0.verify_labelled(
+            group,
+            digest,
+            DECRYPTION_LABEL,
+            &[b, m],
+            &group.generator(),
+            y,
+            a,
+            &ax,
+        )
+    }
+
+    /// Encodes the proof as `c || r`.
+    pub fn to_bytes(&self, group: &G) -> Vec<u8> {
+        self.0.to_bytes(group)
+    }
+
+    /// Decodes a proof produced by [`DecryptionProof::to_bytes`].
+    pub fn from_bytes(group: &G, data: &[u8]) -> Result<Self> {
+        DleqProof::from_bytes(group, data).map(Self)
+    }
+}
+
+/// Schnorr proof of knowledge of `x` such that `y = g^x`, bound to a context string: a
+/// [`SigmaProof`] of [`Relation::dlog`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchnorrProof<G: Group> {
+    /// Fiat–Shamir challenge
+    pub c: G::Scalar,
+    /// Response `w + c * x`
+    pub r: G::Scalar,
+}
+
+const SCHNORR_LABEL: &[u8] = b"elgamal schnorr proof";
+
+impl<G: Group> SchnorrProof<G> {
+    /// Proves knowledge of the private key `x`. The proof only verifies for the same `context`.
+    pub fn prove<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        group: &G,
+        digest: &mut dyn DynDigest,
+        context: &[u8],
+        x: &G::Scalar,
+    ) -> Self {
+        let relation = Relation::dlog(group.generator(), group.exp_generator(x));
+        let (c, r) = prove_relation(rng, group, digest, &Self::label(context), relation, x);
+
+        Self { c, r }
+    }
+
+    /// Verifies the proof for the public key `y` in the given `context`.
+    pub fn verify(
+        &self,
+        group: &G,
+        digest: &mut dyn DynDigest,
+        context: &[u8],
+        y: &G::Element,
+    ) -> Result<()> {
+        if !group.is_element(y) || *y == group.identity() {
+            return Err(Error::InvalidData);
+        }
+
+        let relation = Relation::dlog(group.generator(), y.clone());
+
+        verify_relation(
+            group,
+            digest,
+            &Self::label(context),
+            relation,
+            &self.c,
+            &self.r,
+        )
+    }
+
+    /// Domain label followed by the length-prefixed context.
+    fn label(context: &[u8]) -> Vec<u8> {
+        let mut label = SCHNORR_LABEL.to_vec();
+        label.extend_from_slice(&(context.len() as u64).to_be_bytes());
+        label.extend_from_slice(context);
+
+        label
+    }
+
+    /// Encodes the proof as `c || r`.
+    pub fn to_bytes(&self, group: &G) -> Vec<u8> {
+        DleqProof {
+            c: self.c.clone(),
+            r: self.r.clone(),
+        }
+        .to_bytes(group)
+    }
+
+    /// Decodes a proof produced by [`SchnorrProof::to_bytes`].
+    pub fn from_bytes(group: &G, data: &[u8]) -> Result<Self> {
+        let DleqProof { c, r } = DleqProof::from_bytes(group, data)?;
+
+        Ok(Self { c, r })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use num_bigint::BigUint;
+    use rand::{prelude::StdRng, SeedableRng};
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::algorithms::key_generation;
+    use crate::internal::{encrypt, group_encrypt};
+    use crate::{ElgamalGroup, ElgamalPublicKey, Ristretto255};
+
+    fn dleq<G: Group>(group: &G) {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+
+        let x = group.random_scalar(&mut rng);
+        let g1 = group.generator();
+        let g2 = group.exp_generator(&group.random_scalar(&mut rng));
+        let h1 = group.exp(&g1, &x);
+        let h2 = group.exp(&g2, &x);
+
+        let proof = DleqProof::prove(&mut rng, group, &mut digest, &g1, &g2, &x);
+        proof
+            .verify(group, &mut digest, &g1, &h1, &g2, &h2)
+            .unwrap();
+
+        let decoded = DleqProof::from_bytes(group, &proof.to_bytes(group)).unwrap();
+        assert_eq!(decoded, proof);
+
+        // The same proof seen through the generic sigma framework.
+        let sigma = SigmaProof {
+            challenge: proof.c.clone(),
+            response: Response::Relation(vec![proof.r.clone()]),
+        };
+        let statement = Statement::Relation(Relation::dleq(
+            g1.clone(),
+            h1.clone(),
+            g2.clone(),
+            h2.clone(),
+        ));
+        let label = context_label(group, DLEQ_LABEL, &[]);
+        sigma
+            .verify(group, &mut digest, &label, &statement)
+            .unwrap();
+
+        let h2 = group.mul(&h2, &g1);
+        assert!(proof
+            .verify(group, &mut digest, &g1, &h1, &g2, &h2)
+            .is_err());
+    }
+
+    fn decryption<G: Group>(group: &G) {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+
+        let x = group.random_scalar(&mut rng);
+        let y = group.exp_generator(&x);
+        let m = group.exp_generator(&group.random_scalar(&mut rng));
+        let (a, b) = group_encrypt(&mut rng, group, &y, &m);
+
+        let (decrypted, proof) =
+            DecryptionProof::prove(&mut rng, group, &mut digest, &x, &a, &b).unwrap();
+        assert_eq!(decrypted, m);
+        proof.verify(group, &mut digest, &y, &a, &b, &m).unwrap();
+
+        let wrong = group.mul(&m, &group.generator());
+        assert!(proof
+            .verify(group, &mut digest, &y, &a, &b, &wrong)
+            .is_err());
+        let other = group.exp_generator(&group.random_scalar(&mut rng));
+        assert!(proof
+            .verify(group, &mut digest, &other, &a, &b, &m)
+            .is_err());
+    }
+
+    #[test]
+    fn decryption_proof() {
+        let mut rng = StdRng::from_entropy();
+        decryption(&ElgamalGroup::generate(&mut rng, 256, 128));
+        decryption(&Ristretto255);
+    }
+
+    #[test]
+    fn decryption_proof_small_message() {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+        let group = ElgamalGroup::generate(&mut rng, 256, 128);
+        let (y, x) = key_generation(&mut rng, &group);
+        let key = ElgamalPublicKey::new(group.clone(), y.clone());
+
+        for m in [2u32, 3, 5, 7] {
+            let m = BigUint::from(m);
+            let (a, b) = encrypt(&mut rng, &key, &m);
+
+            let (decrypted, proof) =
+                DecryptionProof::prove(&mut rng, &group, &mut digest, &x, &a, &b).unwrap();
+            assert_eq!(decrypted, m);
+            proof.verify(&group, &mut digest, &y, &a, &b, &m).unwrap();
+            assert!(proof
+                .verify(&group, &mut digest, &y, &a, &b, &(&m + 1u32))
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn schnorr_proof() {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+        let group = Ristretto255;
+
+        let x = group.random_scalar(&mut rng);
+        let y = group.exp_generator(&x);
+        let proof = SchnorrProof::prove(&mut rng, &group, &mut digest, b"alice", &x);
+        proof.verify(&group, &mut digest, b"alice", &y).unwrap();
+
+        let decoded = SchnorrProof::from_bytes(&group, &proof.to_bytes(&group)).unwrap();
+        assert_eq!(decoded, proof);
+
+        assert!(proof.verify(&group, &mut digest, b"bob", &y).is_err());
+        let other = group.mul(&y, &group.generator());
+        assert!(proof.verify(&group, &mut digest, b"alice", &other).is_err());
+    }
+
+    #[test]
+    fn dleq_proof() {
+        let mut rng = StdRng::from_entropy();
+        dleq(&ElgamalGroup::generate(&mut rng, 256, 128));
+        dleq(&Ristretto255);
+    }
+}
@@ -0,0 +1,215 @@
+use std::collections::HashSet;
+
+use digest::DynDigest;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::{Error, Result};
+use crate::group::Group;
+use crate::shuffle::random_permutation;
+
+/// Items of the client hashed into the group and blinded, `H(x)^a`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PsiRequest<G: Group> {
+    pub elements: Vec<G::Element>,
+}
+
+/// Answer of the server to a [`PsiRequest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PsiResponse<G: Group> {
+    /// `H(x)^ab` for every item of the client, in request order unless shuffled
+    pub client: Vec<G::Element>,
+    /// `H(y)^b` for every item of the server, shuffled
+    pub server: Vec<G::Element>,
+    /// Whether `client` was shuffled, revealing only the size of the intersection
+    pub cardinality_only: bool,
+}
+
+/// Client of the Diffie–Hellman private set intersection, which learns the common
+/// items or only their number. Secure against semi-honest parties.
+#[derive(Debug, Clone)]
+pub struct PsiClient<G: Group> {
+    group: G,
+    a: G::Scalar,
+    items: Vec<Vec<u8>>,
+}
+
+/// Server of the private set intersection, which learns only the size of the
+/// client set.
+#[derive(Debug, Clone)]
+pub struct PsiServer<G: Group> {
+    group: G,
+    b: G::Scalar,
+    items: Vec<Vec<u8>>,
+}
+
+const PSI_LABEL: &[u8] = b"elgamal psi";
+
+fn hash_item<G: Group>(group: &G, digest: &mut dyn DynDigest, item: &[u8]) -> G::Element {
+    let mut input = PSI_LABEL.to_vec();
+    input.extend_from_slice(item);
+
+    group.hash_to_element(digest, &input)
+}
+
+fn check_elements<G: Group>(group: &G, elements: &[G::Element]) -> Result<()> {
+    match elements.iter().all(|e| group.is_element(e)) {
+        true => Ok(()),
+        false => Err(Error::InvalidData),
+    }
+}
+
+impl<G: Group> PsiClient<G> {
+    /// Blinds the items of the client with a fresh secret exponent.
+    pub fn new<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        group: &G,
+        digest: &mut dyn DynDigest,
+        items: &[&[u8]],
+    ) -> (Self, PsiRequest<G>) {
+        let a = group.random_scalar(rng);
+        let elements = items
+            .iter()
+            .map(|item| group.exp(&hash_item(group, digest, item), &a))
+            .collect();
+
+        (
+            Self {
+                group: group.clone(),
+                a,
+                items: items.iter().map(|item| item.to_vec()).collect(),
+            },
+            PsiRequest { elements },
+        )
+    }
+
+    /// `H(y)^ba` for every item of the server.
+    fn server_set(&self, response: &PsiResponse<G>) -> Result<HashSet<Vec<u8>>> {
+        let group = &self.group;
+        if response.client.len() != self.items.len() {
+            return Err(Error::InvalidData);
+        }
+        check_elements(group, &response.client)?;
+        check_elements(group, &response.server)?;
+
+        Ok(response
+            .server
+            .iter()
+            .map(|e| group.element_to_bytes(&group.exp(e, &self.a)))
+            .collect())
+    }
+
+    /// Items of the client also held by the server.
+    pub fn intersection(&self, response: &PsiResponse<G>) -> Result<Vec<Vec<u8>>> {
+        if response.cardinality_only {
+            return Err(Error::InvalidState);
+        }
+        let server = self.server_set(response)?;
+
+        Ok(self
+            .items
+            .iter()
+            .zip(&response.client)
+            .filter(|(_, e)| server.contains(&self.group.element_to_bytes(e)))
+            .map(|(item, _)| item.clone())
+            .collect())
+    }
+
+    /// Number of items held by both parties.
+    pub fn cardinality(&self, response: &PsiResponse<G>) -> Result<usize> {
+        let server = self.server_set(response)?;
+
+        Ok(response
+            .client
+            .iter()
+            .filter(|e| server.contains(&self.group.element_to_bytes(e)))
+            .count())
+    }
+}
+
+impl<G: Group> PsiServer<G> {
+    /// Server holding `items`, with a fresh secret exponent.
+    pub fn new<R: RngCore + CryptoRng>(rng: &mut R, group: &G, items: &[&[u8]]) -> Self {
+        Self {
+            group: group.clone(),
+            b: group.random_scalar(rng),
+            items: items.iter().map(|item| item.to_vec()).collect(),
+        }
+    }
+
+    /// Blinds the request again and sends the blinded items of the server in random
+    /// order; with `cardinality_only` the client items are shuffled as well.
+    pub fn respond<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        digest: &mut dyn DynDigest,
+        request: &PsiRequest<G>,
+        cardinality_only: bool,
+    ) -> Result<PsiResponse<G>> {
+        let group = &self.group;
+        check_elements(group, &request.elements)?;
+
+        let mut client: Vec<_> = request
+            .elements
+            .iter()
+            .map(|e| group.exp(e, &self.b))
+            .collect();
+        if cardinality_only {
+            client = random_permutation(rng, client.len())
+                .into_iter()
+                .map(|i| client[i].clone())
+                .collect();
+        }
+
+        let server = random_permutation(rng, self.items.len())
+            .into_iter()
+            .map(|i| group.exp(&hash_item(group, digest, &self.items[i]), &self.b))
+            .collect();
+
+        Ok(PsiResponse {
+            client,
+            server,
+            cardinality_only,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{prelude::StdRng, SeedableRng};
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::{ElgamalGroup, Ristretto255};
+
+    fn intersect<G: Group>(group: &G) {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+        let clients: [&[u8]; 4] = [b"alice", b"bob", b"carol", b"dave"];
+        let servers: [&[u8]; 3] = [b"carol", b"eve", b"alice"];
+
+        let server = PsiServer::new(&mut rng, group, &servers);
+        let (client, request) = PsiClient::new(&mut rng, group, &mut digest, &clients);
+
+        let response = server
+            .respond(&mut rng, &mut digest, &request, false)
+            .unwrap();
+        assert_eq!(
+            client.intersection(&response).unwrap(),
+            vec![b"alice".to_vec(), b"carol".to_vec()]
+        );
+        assert_eq!(client.cardinality(&response).unwrap(), 2);
+
+        let response = server
+            .respond(&mut rng, &mut digest, &request, true)
+            .unwrap();
+        assert_eq!(client.cardinality(&response).unwrap(), 2);
+        assert!(client.intersection(&response).is_err());
+    }
+
+    #[test]
+    fn private_set_intersection() {
+        let mut rng = StdRng::from_entropy();
+        intersect(&ElgamalGroup::generate(&mut rng, 256, 128));
+        intersect(&Ristretto255);
+    }
+}
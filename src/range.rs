@@ -0,0 +1,203 @@
+use digest::DynDigest;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::ballot::DisjunctiveProof;
+use crate::error::{Error, Result};
+use crate::group::Group;
+use crate::internal::group_encrypt_exponent_with;
+
+/// Proof that an exponential El Gamal ciphertext `(g^r, g^v * y^r)` encrypts a value
+/// in `[0, 2^n)`.
+///
+/// The value is decomposed in `n` bits, each encrypted separately with a 0/1 proof.
+/// The randomness of the bits is chosen so that `prod (a_i, b_i)^(2^i) = (a, b)`,
+/// which the verifier checks directly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeProof<G: Group> {
+    /// Encryption of every bit, least significant first
+    pub bits: Vec<(G::Element, G::Element)>,
+    /// 0/1 proof of every bit
+    pub proofs: Vec<DisjunctiveProof<G>>,
+}
+
+impl<G: Group> RangeProof<G> {
+    /// Proves that `(a, b)`, encrypted under `y` with randomness `r`, encrypts `v < 2^n`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        group: &G,
+        digest: &mut dyn DynDigest,
+        y: &G::Element,
+        (a, b): (&G::Element, &G::Element),
+        r: &G::Scalar,
+        v: u64,
+        n: usize,
+    ) -> Result<Self> {
+        Self::check_size(group, n)?;
+        if n < 64 && v >> n != 0 {
+            return Err(Error::InvalidRange);
+        }
+        if group_encrypt_exponent_with(group, y, &group.scalar_from_u64(v), r)
+            != (a.clone(), b.clone())
+        {
+            return Err(Error::InvalidData);
+        }
+
+        // r = sum r_i * 2^i, the last r_i absorbs the difference.
+        let mut randomness: Vec<_> = (0..n - 1).map(|_| group.random_scalar(rng)).collect();
+        let weighted = Self::weights(group, n)
+            .iter()
+            .zip(&randomness)
+            .fold(group.scalar_from_u64(0), |acc, (w, r)| {
+                group.scalar_add(&acc, &group.scalar_mul(w, r))
+            });
+        let last_weight = group
+            .scalar_invert(&Self::weights(group, n)[n - 1])
+            .ok_or(Error::InvalidInverse)?;
+        randomness.push(group.scalar_mul(&group.scalar_sub(r, &weighted), &last_weight));
+
+        let mut bits = Vec::with_capacity(n);
+        let mut proofs = Vec::with_capacity(n);
+        for (i, r) in randomness.iter().enumerate() {
+            let bit = (v >> i) & 1 == 1;
+            let (a, b) =
+                group_encrypt_exponent_with(group, y, &group.scalar_from_u64(bit as u64), r);
+            proofs.push(DisjunctiveProof::prove_bit(
+                rng,
+                group,
+                digest,
+                y,
+                (&a, &b),
+                r,
+                bit,
+            )?);
+            bits.push((a, b));
+        }
+
+        Ok(Self { bits, proofs })
+    }
+
+    /// Verifies that `(a, b)` encrypts a value in `[0, 2^n)` under `y`.
+    pub fn verify(
+        &self,
+        group: &G,
+        digest: &mut dyn DynDigest,
+        y: &G::Element,
+        (a, b): (&G::Element, &G::Element),
+        n: usize,
+    ) -> Result<()> {
+        Self::check_size(group, n)?;
+        if self.bits.len() != n || self.proofs.len() != n {
+            return Err(Error::InvalidData);
+        }
+
+        let mut sum = (group.identity(), group.identity());
+        for (((a_i, b_i), proof), w) in self
+            .bits
+            .iter()
+            .zip(&self.proofs)
+            .zip(Self::weights(group, n))
+        {
+            proof.verify_bit(group, digest, y, (a_i, b_i))?;
+            sum = (
+                group.mul(&sum.0, &group.exp(a_i, &w)),
+                group.mul(&sum.1, &group.exp(b_i, &w)),
+            );
+        }
+
+        match sum == (a.clone(), b.clone()) {
+            true => Ok(()),
+            false => Err(Error::Verification),
+        }
+    }
+
+    /// `2^n` must be far below the group order for the decomposition to be unique.
+    fn check_size(group: &G, n: usize) -> Result<()> {
+        match n > 0 && n <= 64 && n < group.order().bits() {
+            true => Ok(()),
+            false => Err(Error::InvalidRange),
+        }
+    }
+
+    /// `2^i` for every bit.
+    fn weights(group: &G, n: usize) -> Vec<G::Scalar> {
+        let two = group.scalar_from_u64(2);
+        let mut weights = vec![group.scalar_from_u64(1)];
+        for i in 1..n {
+            weights.push(group.scalar_mul(&weights[i - 1], &two));
+        }
+
+        weights
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{prelude::StdRng, SeedableRng};
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::{ElgamalGroup, Ristretto255};
+
+    fn range<G: Group>(group: &G) {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+        let y = group.exp_generator(&group.random_scalar(&mut rng));
+
+        let r = group.random_scalar(&mut rng);
+        let (a, b) = group_encrypt_exponent_with(group, &y, &group.scalar_from_u64(200), &r);
+        let proof =
+            RangeProof::prove(&mut rng, group, &mut digest, &y, (&a, &b), &r, 200, 8).unwrap();
+        proof.verify(group, &mut digest, &y, (&a, &b), 8).unwrap();
+        assert!(proof.verify(group, &mut digest, &y, (&a, &b), 9).is_err());
+
+        let other = group.mul(&b, &group.generator());
+        assert!(proof
+            .verify(group, &mut digest, &y, (&a, &other), 8)
+            .is_err());
+
+        // The prover refuses values of more than n bits.
+        let (a256, b256) = group_encrypt_exponent_with(group, &y, &group.scalar_from_u64(256), &r);
+        assert!(matches!(
+            RangeProof::prove(&mut rng, group, &mut digest, &y, (&a256, &b256), &r, 256, 8),
+            Err(Error::InvalidRange)
+        ));
+
+        // Tampered bit ciphertext.
+        let mut forged = proof.clone();
+        forged.bits[3].1 = group.mul(&forged.bits[3].1, &group.generator());
+        assert!(forged.verify(group, &mut digest, &y, (&a, &b), 8).is_err());
+
+        // Bit proofs swapped between positions.
+        let mut forged = proof.clone();
+        forged.proofs.swap(0, 1);
+        assert!(forged.verify(group, &mut digest, &y, (&a, &b), 8).is_err());
+
+        // Valid bits of another encryption of the same value do not multiply back to
+        // `(a, b)`.
+        let r2 = group.random_scalar(&mut rng);
+        let (a2, b2) = group_encrypt_exponent_with(group, &y, &group.scalar_from_u64(200), &r2);
+        let other =
+            RangeProof::prove(&mut rng, group, &mut digest, &y, (&a2, &b2), &r2, 200, 8).unwrap();
+        assert!(matches!(
+            other.verify(group, &mut digest, &y, (&a, &b), 8),
+            Err(Error::Verification)
+        ));
+
+        // Bits of 200 rotated by one position encrypt 145.
+        let mut shifted = proof.clone();
+        shifted.bits.rotate_right(1);
+        shifted.proofs.rotate_right(1);
+        assert!(matches!(
+            shifted.verify(group, &mut digest, &y, (&a, &b), 8),
+            Err(Error::Verification)
+        ));
+    }
+
+    #[test]
+    fn range_proof() {
+        let mut rng = StdRng::from_entropy();
+        range(&ElgamalGroup::generate(&mut rng, 256, 128));
+        range(&Ristretto255);
+    }
+}
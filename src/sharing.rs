@@ -0,0 +1,146 @@
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::{Error, Result};
+use crate::group::Group;
+
+/// Polynomial with coefficients modulo the order of a group, `coefficients[0]` being the secret.
+#[derive(Debug, Clone)]
+pub(crate) struct Polynomial<G: Group> {
+    coefficients: Vec<G::Scalar>,
+}
+
+impl<G: Group> Polynomial<G> {
+    /// Random polynomial of degree `threshold - 1` with the given constant term.
+    pub(crate) fn random<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        group: &G,
+        secret: G::Scalar,
+        threshold: usize,
+    ) -> Self {
+        let mut coefficients = Vec::with_capacity(threshold);
+        coefficients.push(secret);
+        for _ in 1..threshold {
+            coefficients.push(group.random_scalar(rng));
+        }
+
+        Self { coefficients }
+    }
+
+    /// Value of the polynomial at `index`.
+    pub(crate) fn evaluate(&self, group: &G, index: u32) -> G::Scalar {
+        let x = group.scalar_from_u64(index as u64);
+
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(group.scalar_from_u64(0), |acc, coefficient| {
+                group.scalar_add(&group.scalar_mul(&acc, &x), coefficient)
+            })
+    }
+
+    /// Feldman commitments `g^a_k` to every coefficient.
+    pub(crate) fn commitments(&self, group: &G) -> Vec<G::Element> {
+        self.coefficients
+            .iter()
+            .map(|coefficient| group.exp_generator(coefficient))
+            .collect()
+    }
+}
+
+/// Evaluates committed polynomial in the exponent: `prod C_k^(index^k)`.
+pub(crate) fn commitments_evaluate<G: Group>(
+    group: &G,
+    commitments: &[G::Element],
+    index: u32,
+) -> G::Element {
+    let x = group.scalar_from_u64(index as u64);
+
+    commitments.iter().rev().fold(group.identity(), |acc, c| {
+        group.mul(&group.exp(&acc, &x), c)
+    })
+}
+
+/// Checks a share against the Feldman commitments of its dealer.
+pub(crate) fn verify_share<G: Group>(
+    group: &G,
+    commitments: &[G::Element],
+    index: u32,
+    share: &G::Scalar,
+) -> bool {
+    group.exp_generator(share) == commitments_evaluate(group, commitments, index)
+}
+
+/// Lagrange coefficient at zero of `index` for the set `indices`.
+pub(crate) fn lagrange_coefficient<G: Group>(
+    group: &G,
+    index: u32,
+    indices: &[u32],
+) -> Result<G::Scalar> {
+    let i = group.scalar_from_u64(index as u64);
+    let mut numerator = group.scalar_from_u64(1);
+    let mut denominator = group.scalar_from_u64(1);
+
+    for &other in indices.iter().filter(|&&other| other != index) {
+        let j = group.scalar_from_u64(other as u64);
+        numerator = group.scalar_mul(&numerator, &j);
+        denominator = group.scalar_mul(&denominator, &group.scalar_sub(&j, &i));
+    }
+
+    let denominator = group
+        .scalar_invert(&denominator)
+        .ok_or(Error::InvalidInverse)?;
+
+    Ok(group.scalar_mul(&numerator, &denominator))
+}
+
+/// Checks that share indices are non-zero and pairwise distinct.
+pub(crate) fn check_indices(indices: &[u32]) -> Result<()> {
+    for (position, index) in indices.iter().enumerate() {
+        if *index == 0 || indices[..position].contains(index) {
+            return Err(Error::InvalidShare);
+        }
+    }
+
+    Ok(())
+}
+
+/// Interpolates the secret from `(index, share)` pairs.
+pub(crate) fn interpolate<G: Group>(group: &G, shares: &[(u32, G::Scalar)]) -> Result<G::Scalar> {
+    let indices: Vec<u32> = shares.iter().map(|(index, _)| *index).collect();
+    check_indices(&indices)?;
+
+    shares
+        .iter()
+        .try_fold(group.scalar_from_u64(0), |acc, (index, share)| {
+            let lambda = lagrange_coefficient(group, *index, &indices)?;
+            Ok(group.scalar_add(&acc, &group.scalar_mul(&lambda, share)))
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{prelude::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::ElgamalGroup;
+
+    #[test]
+    fn share_verify() {
+        let mut rng = StdRng::from_entropy();
+        let group = ElgamalGroup::generate(&mut rng, 128, 64);
+        let secret = group.random_scalar(&mut rng);
+
+        let polynomial = Polynomial::random(&mut rng, &group, secret.clone(), 3);
+        let commitments = polynomial.commitments(&group);
+        let shares: Vec<_> = (1..=5)
+            .map(|index| (index, polynomial.evaluate(&group, index)))
+            .collect();
+
+        for (index, share) in shares.iter() {
+            assert!(verify_share(&group, &commitments, *index, share));
+        }
+
+        assert!(!verify_share(&group, &commitments, 1, &shares[1].1));
+        assert_eq!(commitments[0], group.exp_generator(&secret));
+    }
+}
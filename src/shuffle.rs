@@ -0,0 +1,518 @@
+use digest::DynDigest;
+use num_bigint::{BigUint, RandBigInt};
+use num_traits::ToPrimitive;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::{Error, Result};
+use crate::group::Group;
+use crate::internal::group_encrypt_with;
+use crate::keys::{ElgamalGroup, ElgamalPublicKey};
+use crate::transcript::Transcript;
+
+/// Pair `(a, b)` of ciphertext components.
+type Ciphertext<G> = (<G as Group>::Element, <G as Group>::Element);
+/// Shuffled ciphertexts with their proof.
+type Shuffle<G> = (Vec<Ciphertext<G>>, ShuffleProof<G>);
+
+/// Terelius–Wikström proof that a list of ciphertexts is a permutation and
+/// re-encryption of another list, without revealing the permutation.
+///
+/// Output `i` must be a re-encryption of input `permutation[i]`. The prover commits
+/// to the permutation matrix, then shows in a single sigma protocol that the commitment
+/// opens to a permutation (product and chained commitments) and that the outputs raised
+/// to the permuted challenges equal the inputs raised to the challenges up to a
+/// re-encryption.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShuffleProof<G: Group> {
+    /// Commitments `c_j = g^r_j * h_i` to the columns of the permutation matrix
+    pub permutation_commitments: Vec<G::Element>,
+    /// Chained commitments `ĉ_i = g^R_i * ĉ_{i-1}^u'_i`, `ĉ_0 = h`
+    pub chain_commitments: Vec<G::Element>,
+    /// Commitments `t_1`, `t_2`, `t_3`, `t_4a`, `t_4b` of the sigma protocol
+    pub t: [G::Element; 5],
+    /// Commitments `t̂_i` of the chain
+    pub t_hat: Vec<G::Element>,
+    /// Responses `s_1`, `s_2`, `s_3`, `s_4`
+    pub s: [G::Scalar; 4],
+    /// Responses `ŝ_i` of the chain
+    pub s_hat: Vec<G::Scalar>,
+    /// Responses `s'_i` for the permuted challenges
+    pub s_prime: Vec<G::Scalar>,
+}
+
+const SHUFFLE_LABEL: &[u8] = b"elgamal shuffle proof";
+const GENERATOR_LABEL: &[u8] = b"elgamal shuffle generator";
+
+/// Independent generators `h, h_1, ..., h_n`.
+fn generators<G: Group>(group: &G, digest: &mut dyn DynDigest, n: usize) -> Vec<G::Element> {
+    (0..=n as u64)
+        .map(|i| {
+            let mut input = GENERATOR_LABEL.to_vec();
+            input.extend_from_slice(&i.to_be_bytes());
+            group.hash_to_element(digest, &input)
+        })
+        .collect()
+}
+
+/// Absorbs the public key and both lists of ciphertexts.
+fn absorb<G: Group>(
+    group: &G,
+    transcript: &mut Transcript,
+    y: &G::Element,
+    input: &[Ciphertext<G>],
+    output: &[Ciphertext<G>],
+) {
+    transcript.append_element(group, b"y", y);
+    for (label, ciphertexts) in [(&b"input"[..], input), (b"output", output)] {
+        transcript.append_message(b"length", &(ciphertexts.len() as u64).to_be_bytes());
+        for (a, b) in ciphertexts {
+            transcript.append_element(group, label, a);
+            transcript.append_element(group, label, b);
+        }
+    }
+}
+
+/// Challenges `u_j`, one per input ciphertext.
+fn challenges<G: Group>(
+    group: &G,
+    digest: &mut dyn DynDigest,
+    y: &G::Element,
+    input: &[Ciphertext<G>],
+    output: &[Ciphertext<G>],
+    commitments: &[G::Element],
+) -> Vec<G::Scalar> {
+    let mut transcript = Transcript::new(digest, SHUFFLE_LABEL);
+    absorb(group, &mut transcript, y, input, output);
+    for c in commitments {
+        transcript.append_element(group, b"c", c);
+    }
+
+    (0..input.len())
+        .map(|_| transcript.challenge_scalar(group, b"u"))
+        .collect()
+}
+
+/// `prod bases_i^exponents_i`
+fn multi_exp<G: Group>(group: &G, bases: &[G::Element], exponents: &[G::Scalar]) -> G::Element {
+    bases
+        .iter()
+        .zip(exponents)
+        .fold(group.identity(), |acc, (base, e)| {
+            group.mul(&acc, &group.exp(base, e))
+        })
+}
+
+fn scalar_sum<G: Group>(group: &G, scalars: impl Iterator<Item = G::Scalar>) -> G::Scalar {
+    scalars.fold(group.scalar_from_u64(0), |acc, s| {
+        group.scalar_add(&acc, &s)
+    })
+}
+
+fn scalar_product<G: Group>(group: &G, scalars: &[G::Scalar]) -> G::Scalar {
+    scalars
+        .iter()
+        .fold(group.scalar_from_u64(1), |acc, s| group.scalar_mul(&acc, s))
+}
+
+/// Uniformly random permutation of `0..n` (Fisher–Yates).
+pub(crate) fn random_permutation<R: RngCore + CryptoRng>(rng: &mut R, n: usize) -> Vec<usize> {
+    let mut permutation: Vec<usize> = (0..n).collect();
+    for i in (1..n).rev() {
+        let j = rng
+            .gen_biguint_below(&BigUint::from(i + 1))
+            .to_usize()
+            .unwrap();
+        permutation.swap(i, j);
+    }
+
+    permutation
+}
+
+/// Permutes and re-encrypts `ciphertexts` under `y`, with a proof of correctness.
+pub fn group_shuffle<G: Group, R: RngCore + CryptoRng>(
+    rng: &mut R,
+    group: &G,
+    digest: &mut dyn DynDigest,
+    y: &G::Element,
+    ciphertexts: &[Ciphertext<G>],
+) -> Result<Shuffle<G>> {
+    let permutation = random_permutation(rng, ciphertexts.len());
+    let randomness: Vec<_> = ciphertexts
+        .iter()
+        .map(|_| group.random_scalar(rng))
+        .collect();
+
+    let output: Vec<_> = permutation
+        .iter()
+        .zip(&randomness)
+        .map(|(&j, r)| {
+            let (a, b) = &ciphertexts[j];
+            let (a1, b1) = group_encrypt_with(group, y, &group.identity(), r);
+            (group.mul(a, &a1), group.mul(b, &b1))
+        })
+        .collect();
+
+    let proof = ShuffleProof::prove(
+        rng,
+        group,
+        digest,
+        y,
+        ciphertexts,
+        &output,
+        &permutation,
+        &randomness,
+    )?;
+
+    Ok((output, proof))
+}
+
+impl<G: Group> ShuffleProof<G> {
+    /// Proves that `output[i]` is `input[permutation[i]]` re-encrypted under `y`
+    /// with `randomness[i]`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        group: &G,
+        digest: &mut dyn DynDigest,
+        y: &G::Element,
+        input: &[Ciphertext<G>],
+        output: &[Ciphertext<G>],
+        permutation: &[usize],
+        randomness: &[G::Scalar],
+    ) -> Result<Self> {
+        let n = input.len();
+        if n == 0 || output.len() != n || permutation.len() != n || randomness.len() != n {
+            return Err(Error::InvalidData);
+        }
+        let mut seen = vec![false; n];
+        for &j in permutation {
+            if j >= n || seen[j] {
+                return Err(Error::InvalidData);
+            }
+            seen[j] = true;
+        }
+
+        let g = group.generator();
+        let generators = generators(group, digest, n);
+        let (h, hs) = (&generators[0], &generators[1..]);
+
+        // Commitment to the permutation matrix: column `permutation[i]` holds `h_i`.
+        let r: Vec<_> = (0..n).map(|_| group.random_scalar(rng)).collect();
+        let mut c = vec![group.identity(); n];
+        for (i, &j) in permutation.iter().enumerate() {
+            c[j] = group.mul(&group.exp_generator(&r[j]), &hs[i]);
+        }
+
+        let u = challenges(group, digest, y, input, output, &c);
+        let u_prime: Vec<_> = permutation.iter().map(|&j| u[j].clone()).collect();
+
+        // Commitment chain to the product of the permuted challenges.
+        let r_hat: Vec<_> = (0..n).map(|_| group.random_scalar(rng)).collect();
+        let mut c_hat = Vec::with_capacity(n);
+        for i in 0..n {
+            let previous = if i == 0 { h } else { &c_hat[i - 1] };
+            c_hat.push(group.mul(
+                &group.exp_generator(&r_hat[i]),
+                &group.exp(previous, &u_prime[i]),
+            ));
+        }
+
+        // Witnesses.
+        let r_bar = scalar_sum(group, r.iter().cloned());
+        let mut v = group.scalar_from_u64(1);
+        let mut r_chain = group.scalar_from_u64(0);
+        for i in (0..n).rev() {
+            r_chain = group.scalar_add(&r_chain, &group.scalar_mul(&r_hat[i], &v));
+            v = group.scalar_mul(&v, &u_prime[i]);
+        }
+        let r_tilde = scalar_sum(group, r.iter().zip(&u).map(|(r, u)| group.scalar_mul(r, u)));
+        let r_prime = scalar_sum(
+            group,
+            randomness
+                .iter()
+                .zip(&u_prime)
+                .map(|(r, u)| group.scalar_mul(r, u)),
+        );
+
+        // Sigma protocol commitments.
+        let w: Vec<_> = (0..4).map(|_| group.random_scalar(rng)).collect();
+        let w_hat: Vec<_> = (0..n).map(|_| group.random_scalar(rng)).collect();
+        let w_prime: Vec<_> = (0..n).map(|_| group.random_scalar(rng)).collect();
+
+        let (a_out, b_out): (Vec<_>, Vec<_>) = output.iter().cloned().unzip();
+        let neg_w4 = group.scalar_neg(&w[3]);
+        let t = [
+            group.exp_generator(&w[0]),
+            group.exp_generator(&w[1]),
+            group.mul(&group.exp_generator(&w[2]), &multi_exp(group, hs, &w_prime)),
+            group.mul(&multi_exp(group, &a_out, &w_prime), &group.exp(&g, &neg_w4)),
+            group.mul(&multi_exp(group, &b_out, &w_prime), &group.exp(y, &neg_w4)),
+        ];
+        let t_hat: Vec<_> = (0..n)
+            .map(|i| {
+                let previous = if i == 0 { h } else { &c_hat[i - 1] };
+                group.mul(
+                    &group.exp_generator(&w_hat[i]),
+                    &group.exp(previous, &w_prime[i]),
+                )
+            })
+            .collect();
+
+        let challenge = Self::challenge(group, digest, y, input, output, &c, &c_hat, &t, &t_hat);
+        let respond =
+            |w: &G::Scalar, x: &G::Scalar| group.scalar_add(w, &group.scalar_mul(&challenge, x));
+
+        Ok(Self {
+            s: [
+                respond(&w[0], &r_bar),
+                respond(&w[1], &r_chain),
+                respond(&w[2], &r_tilde),
+                respond(&w[3], &r_prime),
+            ],
+            s_hat: w_hat
+                .iter()
+                .zip(&r_hat)
+                .map(|(w, x)| respond(w, x))
+                .collect(),
+            s_prime: w_prime
+                .iter()
+                .zip(&u_prime)
+                .map(|(w, x)| respond(w, x))
+                .collect(),
+            permutation_commitments: c,
+            chain_commitments: c_hat,
+            t,
+            t_hat,
+        })
+    }
+
+    /// Verifies that `output` is a permutation and re-encryption of `input` under `y`.
+    pub fn verify(
+        &self,
+        group: &G,
+        digest: &mut dyn DynDigest,
+        y: &G::Element,
+        input: &[Ciphertext<G>],
+        output: &[Ciphertext<G>],
+    ) -> Result<()> {
+        let n = input.len();
+        if n == 0
+            || output.len() != n
+            || self.permutation_commitments.len() != n
+            || self.chain_commitments.len() != n
+            || self.t_hat.len() != n
+            || self.s_hat.len() != n
+            || self.s_prime.len() != n
+        {
+            return Err(Error::InvalidData);
+        }
+        let valid = std::iter::once(y)
+            .chain(input.iter().chain(output).flat_map(|(a, b)| [a, b]))
+            .chain(&self.permutation_commitments)
+            .chain(&self.chain_commitments)
+            .chain(&self.t)
+            .chain(&self.t_hat)
+            .all(|element| group.is_element(element));
+        if !valid {
+            return Err(Error::InvalidData);
+        }
+
+        let g = group.generator();
+        let generators = generators(group, digest, n);
+        let (h, hs) = (&generators[0], &generators[1..]);
+        let c = &self.permutation_commitments;
+        let c_hat = &self.chain_commitments;
+
+        let u = challenges(group, digest, y, input, output, c);
+        let challenge = Self::challenge(
+            group,
+            digest,
+            y,
+            input,
+            output,
+            c,
+            c_hat,
+            &self.t,
+            &self.t_hat,
+        );
+        let invert = |e: &G::Element| group.invert(e).ok_or(Error::InvalidInverse);
+
+        // Statements.
+        let product_c = c.iter().fold(group.identity(), |acc, c| group.mul(&acc, c));
+        let product_h = hs
+            .iter()
+            .fold(group.identity(), |acc, h| group.mul(&acc, h));
+        let c_bar = group.mul(&product_c, &invert(&product_h)?);
+        let h_u = group.exp(h, &scalar_product(group, &u));
+        let c_chain = group.mul(&c_hat[n - 1], &invert(&h_u)?);
+        let c_tilde = multi_exp(group, c, &u);
+        let (a_in, b_in): (Vec<_>, Vec<_>) = input.iter().cloned().unzip();
+        let (a_out, b_out): (Vec<_>, Vec<_>) = output.iter().cloned().unzip();
+        let a_u = multi_exp(group, &a_in, &u);
+        let b_u = multi_exp(group, &b_in, &u);
+
+        // `t == lhs / statement^c` for every relation.
+        let check = |lhs: G::Element, statement: &G::Element, t: &G::Element| -> Result<bool> {
+            let expected = group.mul(&lhs, &invert(&group.exp(statement, &challenge))?);
+            Ok(expected == *t)
+        };
+
+        let s = &self.s;
+        let neg_s4 = group.scalar_neg(&s[3]);
+        let mut valid = check(group.exp_generator(&s[0]), &c_bar, &self.t[0])?
+            && check(group.exp_generator(&s[1]), &c_chain, &self.t[1])?
+            && check(
+                group.mul(
+                    &group.exp_generator(&s[2]),
+                    &multi_exp(group, hs, &self.s_prime),
+                ),
+                &c_tilde,
+                &self.t[2],
+            )?
+            && check(
+                group.mul(
+                    &multi_exp(group, &a_out, &self.s_prime),
+                    &group.exp(&g, &neg_s4),
+                ),
+                &a_u,
+                &self.t[3],
+            )?
+            && check(
+                group.mul(
+                    &multi_exp(group, &b_out, &self.s_prime),
+                    &group.exp(y, &neg_s4),
+                ),
+                &b_u,
+                &self.t[4],
+            )?;
+
+        for i in 0..n {
+            let previous = if i == 0 { h } else { &c_hat[i - 1] };
+            let lhs = group.mul(
+                &group.exp_generator(&self.s_hat[i]),
+                &group.exp(previous, &self.s_prime[i]),
+            );
+            valid = valid && check(lhs, &c_hat[i], &self.t_hat[i])?;
+        }
+
+        match valid {
+            true => Ok(()),
+            false => Err(Error::Verification),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn challenge(
+        group: &G,
+        digest: &mut dyn DynDigest,
+        y: &G::Element,
+        input: &[Ciphertext<G>],
+        output: &[Ciphertext<G>],
+        c: &[G::Element],
+        c_hat: &[G::Element],
+        t: &[G::Element],
+        t_hat: &[G::Element],
+    ) -> G::Scalar {
+        let mut transcript = Transcript::new(digest, SHUFFLE_LABEL);
+        absorb(group, &mut transcript, y, input, output);
+        for (label, elements) in [
+            (&b"c"[..], c),
+            (b"c_hat", c_hat),
+            (b"t", t),
+            (b"t_hat", t_hat),
+        ] {
+            for element in elements {
+                transcript.append_element(group, label, element);
+            }
+        }
+
+        transcript.challenge_scalar(group, b"challenge")
+    }
+}
+
+/// Verifiable shuffle of ciphertexts produced under `key`, see [`group_shuffle`].
+pub fn shuffle<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    digest: &mut dyn DynDigest,
+    key: &ElgamalPublicKey,
+    ciphertexts: &[(BigUint, BigUint)],
+) -> Result<Shuffle<ElgamalGroup>> {
+    group_shuffle(rng, key.group(), digest, key.get_y(), ciphertexts)
+}
+
+/// Verifies a shuffle produced by [`shuffle`].
+pub fn verify_shuffle(
+    digest: &mut dyn DynDigest,
+    key: &ElgamalPublicKey,
+    input: &[(BigUint, BigUint)],
+    output: &[(BigUint, BigUint)],
+    proof: &ShuffleProof<ElgamalGroup>,
+) -> Result<()> {
+    proof.verify(key.group(), digest, key.get_y(), input, output)
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{prelude::StdRng, SeedableRng};
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::internal::{group_decrypt, group_encrypt, group_reencrypt};
+    use crate::{elgamal_key_generate, Ristretto255};
+
+    #[test]
+    fn shuffle_verify() {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+        let group = Ristretto255;
+        let x = group.random_scalar(&mut rng);
+        let y = group.exp_generator(&x);
+
+        let messages: Vec<_> = (1..=5)
+            .map(|m| group.exp_generator(&group.scalar_from_u64(m)))
+            .collect();
+        let input: Vec<_> = messages
+            .iter()
+            .map(|m| group_encrypt(&mut rng, &group, &y, m))
+            .collect();
+
+        let (output, proof) = group_shuffle(&mut rng, &group, &mut digest, &y, &input).unwrap();
+        proof
+            .verify(&group, &mut digest, &y, &input, &output)
+            .unwrap();
+
+        let mut decrypted: Vec<_> = output
+            .iter()
+            .map(|(a, b)| group_decrypt(&group, &x, a, b).unwrap())
+            .collect();
+        assert_ne!(output, input);
+        for m in messages.iter() {
+            let position = decrypted.iter().position(|d| d == m).unwrap();
+            decrypted.remove(position);
+        }
+
+        // A ciphertext replaced by a re-encryption of another one is detected.
+        let mut forged = output.clone();
+        let (a, b) = &input[0];
+        forged[0] = group_reencrypt(&mut rng, &group, &y, a, b);
+        forged[1] = group_reencrypt(&mut rng, &group, &y, a, b);
+        assert!(proof
+            .verify(&group, &mut digest, &y, &input, &forged)
+            .is_err());
+    }
+
+    #[test]
+    fn shuffle_elgamal_key() {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+        let group = ElgamalGroup::generate(&mut rng, 256, 128);
+        let (public, _) = elgamal_key_generate(&mut rng, &group);
+
+        let input: Vec<_> = (2..6u32)
+            .map(|m| crate::encrypt(&mut rng, &public, &group.exp_generator(&BigUint::from(m))))
+            .collect();
+        let (output, proof) = shuffle(&mut rng, &mut digest, &public, &input).unwrap();
+        verify_shuffle(&mut digest, &public, &input, &output, &proof).unwrap();
+        assert!(verify_shuffle(&mut digest, &public, &output, &input, &proof).is_err());
+    }
+}
@@ -0,0 +1,593 @@
+use digest::DynDigest;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::{Error, Result};
+use crate::group::Group;
+use crate::transcript::Transcript;
+
+/// `lhs = prod base^x_index` over the witness vector `x`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Equation<G: Group> {
+    /// Public element
+    pub lhs: G::Element,
+    /// `(index of the witness, base)` pairs
+    pub terms: Vec<(usize, G::Element)>,
+}
+
+/// System of discrete-log equations sharing a witness vector, e.g. `y = g^x`
+/// (knowledge of a key) or `y = g^x, d = a^x` (correct decryption).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relation<G: Group> {
+    /// Size of the witness vector
+    pub witnesses: usize,
+    pub equations: Vec<Equation<G>>,
+}
+
+/// Statement proven by a sigma protocol: a relation or an AND / OR composition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Statement<G: Group> {
+    Relation(Relation<G>),
+    /// Every sub-statement holds
+    And(Vec<Statement<G>>),
+    /// At least one sub-statement holds
+    Or(Vec<Statement<G>>),
+}
+
+/// Witness matching the shape of a [`Statement`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Witness<G: Group> {
+    Relation(Vec<G::Scalar>),
+    And(Vec<Witness<G>>),
+    /// Index of the true branch and its witness
+    Or(usize, Box<Witness<G>>),
+}
+
+/// Response of the prover, matching the shape of a [`Statement`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Response<G: Group> {
+    Relation(Vec<G::Scalar>),
+    And(Vec<Response<G>>),
+    /// Challenge and response of every branch
+    Or(Vec<(G::Scalar, Response<G>)>),
+}
+
+/// Secret state of the prover between commitment and response.
+#[derive(Debug, Clone)]
+pub enum ProverState<G: Group> {
+    Relation(Vec<G::Scalar>),
+    And(Vec<ProverState<G>>),
+    Or {
+        index: usize,
+        state: Box<ProverState<G>>,
+        /// Simulated transcripts of the other branches
+        simulated: Vec<Option<(G::Scalar, Response<G>)>>,
+    },
+}
+
+impl<G: Group> Relation<G> {
+    /// Knowledge of `x` such that `h = g^x`.
+    pub fn dlog(g: G::Element, h: G::Element) -> Self {
+        Self {
+            witnesses: 1,
+            equations: vec![Equation {
+                lhs: h,
+                terms: vec![(0, g)],
+            }],
+        }
+    }
+
+    /// Knowledge of `x` such that `h1 = g1^x` and `h2 = g2^x`.
+    pub fn dleq(g1: G::Element, h1: G::Element, g2: G::Element, h2: G::Element) -> Self {
+        Self {
+            witnesses: 1,
+            equations: vec![
+                Equation {
+                    lhs: h1,
+                    terms: vec![(0, g1)],
+                },
+                Equation {
+                    lhs: h2,
+                    terms: vec![(0, g2)],
+                },
+            ],
+        }
+    }
+
+    /// `m` is the decryption of `(a, b)` under `y`: `y = g^x` and `b / m = a^x`.
+    pub fn decryption(
+        group: &G,
+        y: &G::Element,
+        (a, b): (&G::Element, &G::Element),
+        m: &G::Element,
+    ) -> Result<Self> {
+        let m = group.invert(m).ok_or(Error::InvalidInverse)?;
+
+        Ok(Self::dleq(
+            group.generator(),
+            y.clone(),
+            a.clone(),
+            group.mul(b, &m),
+        ))
+    }
+
+    /// `(a1, b1)` is a re-encryption of `(a, b)` under `y`: `a1 / a = g^r` and
+    /// `b1 / b = y^r`.
+    pub fn reencryption(
+        group: &G,
+        y: &G::Element,
+        (a, b): (&G::Element, &G::Element),
+        (a1, b1): (&G::Element, &G::Element),
+    ) -> Result<Self> {
+        let a = group.invert(a).ok_or(Error::InvalidInverse)?;
+        let b = group.invert(b).ok_or(Error::InvalidInverse)?;
+
+        Ok(Self::dleq(
+            group.generator(),
+            group.mul(a1, &a),
+            y.clone(),
+            group.mul(b1, &b),
+        ))
+    }
+
+    fn check_response(&self, s: &[G::Scalar]) -> Result<()> {
+        let valid = s.len() == self.witnesses
+            && self
+                .equations
+                .iter()
+                .flat_map(|equation| &equation.terms)
+                .all(|(index, _)| *index < self.witnesses);
+
+        match valid {
+            true => Ok(()),
+            false => Err(Error::InvalidData),
+        }
+    }
+
+    /// `prod base^e_index` for every equation.
+    fn evaluate(&self, group: &G, e: &[G::Scalar]) -> Vec<G::Element> {
+        self.equations
+            .iter()
+            .map(|equation| {
+                equation
+                    .terms
+                    .iter()
+                    .fold(group.identity(), |acc, (index, base)| {
+                        group.mul(&acc, &group.exp(base, &e[*index]))
+                    })
+            })
+            .collect()
+    }
+}
+
+impl<G: Group> Statement<G> {
+    /// Public elements of the statement, absorbed by the Fiat–Shamir challenge.
+    pub fn elements(&self) -> Vec<&G::Element> {
+        match self {
+            Statement::Relation(relation) => relation
+                .equations
+                .iter()
+                .flat_map(|equation| {
+                    std::iter::once(&equation.lhs).chain(equation.terms.iter().map(|(_, b)| b))
+                })
+                .collect(),
+            Statement::And(statements) | Statement::Or(statements) => {
+                statements.iter().flat_map(|s| s.elements()).collect()
+            }
+        }
+    }
+
+    /// Absorbs the canonical encoding of the statement: its shape (composition,
+    /// sizes, equation boundaries and witness indices) together with its elements.
+    fn absorb(&self, group: &G, transcript: &mut Transcript) {
+        let length = |n: usize| (n as u64).to_be_bytes();
+        match self {
+            Statement::Relation(relation) => {
+                transcript.append_message(b"witnesses", &length(relation.witnesses));
+                transcript.append_message(b"equations", &length(relation.equations.len()));
+                for equation in &relation.equations {
+                    transcript.append_element(group, b"lhs", &equation.lhs);
+                    transcript.append_message(b"terms", &length(equation.terms.len()));
+                    for (index, base) in &equation.terms {
+                        transcript.append_message(b"index", &length(*index));
+                        transcript.append_element(group, b"base", base);
+                    }
+                }
+            }
+            Statement::And(statements) | Statement::Or(statements) => {
+                let tag: &[u8] = match self {
+                    Statement::And(_) => b"and",
+                    _ => b"or",
+                };
+                transcript.append_message(tag, &length(statements.len()));
+                for statement in statements {
+                    statement.absorb(group, transcript);
+                }
+            }
+        }
+    }
+
+    /// First move of the prover: commitments and the state needed to respond.
+    pub fn commit<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        group: &G,
+        witness: &Witness<G>,
+    ) -> Result<(ProverState<G>, Vec<G::Element>)> {
+        match (self, witness) {
+            (Statement::Relation(relation), Witness::Relation(x)) => {
+                relation.check_response(x)?;
+                let w: Vec<_> = x.iter().map(|_| group.random_scalar(rng)).collect();
+                let t = relation.evaluate(group, &w);
+
+                Ok((ProverState::Relation(w), t))
+            }
+            (Statement::And(statements), Witness::And(witnesses))
+                if statements.len() == witnesses.len() =>
+            {
+                let mut states = Vec::with_capacity(statements.len());
+                let mut commitments = Vec::new();
+                for (statement, witness) in statements.iter().zip(witnesses) {
+                    let (state, t) = statement.commit(rng, group, witness)?;
+                    states.push(state);
+                    commitments.extend(t);
+                }
+
+                Ok((ProverState::And(states), commitments))
+            }
+            (Statement::Or(statements), Witness::Or(index, witness))
+                if *index < statements.len() =>
+            {
+                let mut real = None;
+                let mut simulated = Vec::with_capacity(statements.len());
+                let mut commitments = Vec::new();
+                for (j, statement) in statements.iter().enumerate() {
+                    if j == *index {
+                        let (state, t) = statement.commit(rng, group, witness)?;
+                        real = Some(state);
+                        simulated.push(None);
+                        commitments.extend(t);
+                    } else {
+                        let c = group.random_scalar(rng);
+                        let response = statement.simulate(rng, group, &c)?;
+                        commitments.extend(statement.recompute(group, &c, &response)?);
+                        simulated.push(Some((c, response)));
+                    }
+                }
+
+                let state = Box::new(real.ok_or(Error::InvalidData)?);
+                Ok((
+                    ProverState::Or {
+                        index: *index,
+                        state,
+                        simulated,
+                    },
+                    commitments,
+                ))
+            }
+            _ => Err(Error::InvalidData),
+        }
+    }
+
+    /// Third move of the prover: response to the challenge `c`.
+    pub fn respond(
+        &self,
+        group: &G,
+        witness: &Witness<G>,
+        state: ProverState<G>,
+        c: &G::Scalar,
+    ) -> Result<Response<G>> {
+        match (self, witness, state) {
+            (Statement::Relation(_), Witness::Relation(x), ProverState::Relation(w)) => {
+                Ok(Response::Relation(
+                    w.iter()
+                        .zip(x)
+                        .map(|(w, x)| group.scalar_add(w, &group.scalar_mul(c, x)))
+                        .collect(),
+                ))
+            }
+            (Statement::And(statements), Witness::And(witnesses), ProverState::And(states)) => {
+                statements
+                    .iter()
+                    .zip(witnesses)
+                    .zip(states)
+                    .map(|((statement, witness), state)| {
+                        statement.respond(group, witness, state, c)
+                    })
+                    .collect::<Result<_>>()
+                    .map(Response::And)
+            }
+            (
+                Statement::Or(statements),
+                Witness::Or(_, witness),
+                ProverState::Or {
+                    index,
+                    state,
+                    simulated,
+                },
+            ) => {
+                let others = simulated
+                    .iter()
+                    .flatten()
+                    .fold(group.scalar_from_u64(0), |acc, (c, _)| {
+                        group.scalar_add(&acc, c)
+                    });
+                let c_real = group.scalar_sub(c, &others);
+                let real = statements[index].respond(group, witness, *state, &c_real)?;
+
+                Ok(Response::Or(
+                    simulated
+                        .into_iter()
+                        .map(|branch| branch.unwrap_or_else(|| (c_real.clone(), real.clone())))
+                        .collect(),
+                ))
+            }
+            _ => Err(Error::InvalidData),
+        }
+    }
+
+    /// Simulator: an accepting response for the challenge `c`, without any witness.
+    /// The matching commitments are given by [`Statement::recompute`].
+    pub fn simulate<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        group: &G,
+        c: &G::Scalar,
+    ) -> Result<Response<G>> {
+        match self {
+            Statement::Relation(relation) => Ok(Response::Relation(
+                (0..relation.witnesses)
+                    .map(|_| group.random_scalar(rng))
+                    .collect(),
+            )),
+            Statement::And(statements) => statements
+                .iter()
+                .map(|statement| statement.simulate(rng, group, c))
+                .collect::<Result<_>>()
+                .map(Response::And),
+            Statement::Or(statements) => {
+                let (last, first) = statements.split_last().ok_or(Error::InvalidData)?;
+                let mut branches = Vec::with_capacity(statements.len());
+                let mut sum = group.scalar_from_u64(0);
+                for statement in first {
+                    let c_j = group.random_scalar(rng);
+                    sum = group.scalar_add(&sum, &c_j);
+                    branches.push((c_j.clone(), statement.simulate(rng, group, &c_j)?));
+                }
+                let c_last = group.scalar_sub(c, &sum);
+                branches.push((c_last.clone(), last.simulate(rng, group, &c_last)?));
+
+                Ok(Response::Or(branches))
+            }
+        }
+    }
+
+    /// Verification equation: recomputes the commitments from the challenge and the
+    /// response, `t = prod base^s / lhs^c`. An accepting transcript reproduces the
+    /// commitments of the prover.
+    pub fn recompute(
+        &self,
+        group: &G,
+        c: &G::Scalar,
+        response: &Response<G>,
+    ) -> Result<Vec<G::Element>> {
+        match (self, response) {
+            (Statement::Relation(relation), Response::Relation(s)) => {
+                relation.check_response(s)?;
+                relation
+                    .evaluate(group, s)
+                    .into_iter()
+                    .zip(&relation.equations)
+                    .map(|(t, equation)| {
+                        let lhs = group
+                            .invert(&group.exp(&equation.lhs, c))
+                            .ok_or(Error::InvalidInverse)?;
+                        Ok(group.mul(&t, &lhs))
+                    })
+                    .collect()
+            }
+            (Statement::And(statements), Response::And(responses))
+                if statements.len() == responses.len() =>
+            {
+                let mut commitments = Vec::new();
+                for (statement, response) in statements.iter().zip(responses) {
+                    commitments.extend(statement.recompute(group, c, response)?);
+                }
+
+                Ok(commitments)
+            }
+            (Statement::Or(statements), Response::Or(branches))
+                if statements.len() == branches.len() =>
+            {
+                let sum = branches
+                    .iter()
+                    .fold(group.scalar_from_u64(0), |acc, (c, _)| {
+                        group.scalar_add(&acc, c)
+                    });
+                if sum != *c {
+                    return Err(Error::Verification);
+                }
+
+                let mut commitments = Vec::new();
+                for (statement, (c, response)) in statements.iter().zip(branches) {
+                    commitments.extend(statement.recompute(group, c, response)?);
+                }
+
+                Ok(commitments)
+            }
+            _ => Err(Error::InvalidData),
+        }
+    }
+}
+
+/// Non-interactive sigma proof obtained with the Fiat–Shamir transform.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SigmaProof<G: Group> {
+    /// `H(label, statement, commitments)`, the statement encoded with its structure
+    pub challenge: G::Scalar,
+    pub response: Response<G>,
+}
+
+impl<G: Group> SigmaProof<G> {
+    /// Proves `statement` with `witness`; `label` separates the proofs of different
+    /// applications.
+    pub fn prove<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        group: &G,
+        digest: &mut dyn DynDigest,
+        label: &[u8],
+        statement: &Statement<G>,
+        witness: &Witness<G>,
+    ) -> Result<Self> {
+        let (state, commitments) = statement.commit(rng, group, witness)?;
+        let challenge = Self::challenge(group, digest, label, statement, &commitments);
+        let response = statement.respond(group, witness, state, &challenge)?;
+
+        Ok(Self {
+            challenge,
+            response,
+        })
+    }
+
+    /// Verifies the proof of `statement` made with the same `label`.
+    pub fn verify(
+        &self,
+        group: &G,
+        digest: &mut dyn DynDigest,
+        label: &[u8],
+        statement: &Statement<G>,
+    ) -> Result<()> {
+        if statement.elements().iter().any(|e| !group.is_element(e)) {
+            return Err(Error::InvalidData);
+        }
+
+        let commitments = statement.recompute(group, &self.challenge, &self.response)?;
+        match Self::challenge(group, digest, label, statement, &commitments) == self.challenge {
+            true => Ok(()),
+            false => Err(Error::Verification),
+        }
+    }
+
+    fn challenge(
+        group: &G,
+        digest: &mut dyn DynDigest,
+        label: &[u8],
+        statement: &Statement<G>,
+        commitments: &[G::Element],
+    ) -> G::Scalar {
+        let mut transcript = Transcript::new(digest, label);
+        statement.absorb(group, &mut transcript);
+        for commitment in commitments {
+            transcript.append_element(group, b"commitment", commitment);
+        }
+
+        transcript.challenge_scalar(group, b"challenge")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{prelude::StdRng, SeedableRng};
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::internal::{group_encrypt, group_reencrypt};
+    use crate::{ElgamalGroup, Ristretto255};
+
+    const LABEL: &[u8] = b"test";
+
+    fn compose<G: Group>(group: &G) {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+
+        let x = group.random_scalar(&mut rng);
+        let y = group.exp_generator(&x);
+        let other = group.exp_generator(&group.random_scalar(&mut rng));
+        let key = Statement::Relation(Relation::dlog(group.generator(), y.clone()));
+        let unknown = Statement::Relation(Relation::dlog(group.generator(), other));
+
+        // Re-encryption and knowledge of the key.
+        let m = group.generator();
+        let (a, b) = group_encrypt(&mut rng, group, &y, &m);
+        let r = group.random_scalar(&mut rng);
+        let (a1, b1) = (
+            group.mul(&a, &group.exp_generator(&r)),
+            group.mul(&b, &group.exp(&y, &r)),
+        );
+        let reencryption =
+            Statement::Relation(Relation::reencryption(group, &y, (&a, &b), (&a1, &b1)).unwrap());
+
+        let and = Statement::And(vec![key.clone(), reencryption.clone()]);
+        let witness = Witness::And(vec![
+            Witness::Relation(vec![x.clone()]),
+            Witness::Relation(vec![r.clone()]),
+        ]);
+        let proof = SigmaProof::prove(&mut rng, group, &mut digest, LABEL, &and, &witness).unwrap();
+        proof.verify(group, &mut digest, LABEL, &and).unwrap();
+        assert!(proof.verify(group, &mut digest, b"other", &and).is_err());
+
+        // Knowledge of one of two keys, in either position.
+        let or = Statement::Or(vec![unknown.clone(), key.clone()]);
+        let witness = Witness::Or(1, Box::new(Witness::Relation(vec![x.clone()])));
+        let proof = SigmaProof::prove(&mut rng, group, &mut digest, LABEL, &or, &witness).unwrap();
+        proof.verify(group, &mut digest, LABEL, &or).unwrap();
+
+        // A wrong witness does not produce an accepting proof.
+        let or = Statement::Or(vec![key, unknown]);
+        let witness = Witness::Or(1, Box::new(Witness::Relation(vec![x])));
+        let proof = SigmaProof::prove(&mut rng, group, &mut digest, LABEL, &or, &witness).unwrap();
+        assert!(proof.verify(group, &mut digest, LABEL, &or).is_err());
+
+        // Simulated transcripts are accepting, including the challenge split of OR.
+        let c = group.random_scalar(&mut rng);
+        let (a2, b2) = group_reencrypt(&mut rng, group, &y, &a, &b);
+        let fake =
+            Statement::Relation(Relation::reencryption(group, &y, (&a, &b), (&a2, &b2)).unwrap());
+        let or = Statement::Or(vec![fake.clone(), fake]);
+        let response = or.simulate(&mut rng, group, &c).unwrap();
+        assert_eq!(or.recompute(group, &c, &response).unwrap().len(), 4);
+        let other = group.scalar_add(&c, &group.scalar_from_u64(1));
+        assert!(or.recompute(group, &other, &response).is_err());
+    }
+
+    #[test]
+    fn sigma_structure() {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+        let group = Ristretto255;
+        let g = group.generator();
+        let h = group.exp_generator(&group.random_scalar(&mut rng));
+
+        // Statements with the same elements but different shapes.
+        let relation = Relation::dleq(g, h, h, g);
+        let mut indices = relation.clone();
+        indices.witnesses = 2;
+        indices.equations[1].terms[0].0 = 1;
+        let mut merged = Relation::dlog(g, h);
+        merged.equations[0].terms.push((0, h));
+        merged.equations[0].terms.push((0, g));
+        let statement = Statement::Relation(relation);
+        let statements = [
+            statement.clone(),
+            Statement::Relation(indices),
+            Statement::Relation(merged),
+            Statement::And(vec![statement.clone()]),
+            Statement::Or(vec![statement.clone()]),
+            Statement::And(vec![Statement::And(vec![statement])]),
+        ];
+
+        let challenges: Vec<_> = statements
+            .iter()
+            .map(|statement| SigmaProof::challenge(&group, &mut digest, LABEL, statement, &[]))
+            .collect();
+        for (i, c) in challenges.iter().enumerate() {
+            assert!(challenges[i + 1..].iter().all(|other| other != c));
+        }
+    }
+
+    #[test]
+    fn sigma_composition() {
+        let mut rng = StdRng::from_entropy();
+        compose(&ElgamalGroup::generate(&mut rng, 256, 128));
+        compose(&Ristretto255);
+    }
+}
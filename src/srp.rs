@@ -0,0 +1,478 @@
+use digest::DynDigest;
+use num_bigint::{BigUint, RandBigInt};
+use num_traits::{Num, One, Zero};
+use rand_core::{CryptoRng, RngCore};
+use subtle::ConstantTimeEq;
+
+use crate::error::{Error, Result};
+use crate::group::Group;
+use crate::keys::{ElgamalGroup, ElgamalGroupElements};
+
+// Safe primes of RFC 5054 appendix A; the 3072 and 4096-bit ones are the MODP groups
+// of RFC 3526.
+const N_1024: &str = concat!(
+    "EEAF0AB9ADB38DD69C33F80AFA8FC5E86072618775FF3C0B9EA2314C9C256576",
+    "D674DF7496EA81D3383B4813D692C6E0E0D5D8E250B98BE48E495C1D6089DAD1",
+    "5DC7D7B46154D6B6CE8EF4AD69B15D4982559B297BCF1885C529F566660E57EC",
+    "68EDBC3C05726CC02FD4CBF4976EAA9AFD5138FE8376435B9FC61D2FC0EB06E3",
+);
+const N_1536: &str = concat!(
+    "9DEF3CAFB939277AB1F12A8617A47BBBDBA51DF499AC4C80BEEEA9614B19CC4D",
+    "5F4F5F556E27CBDE51C6A94BE4607A291558903BA0D0F84380B655BB9A22E8DC",
+    "DF028A7CEC67F0D08134B1C8B97989149B609E0BE3BAB63D47548381DBC5B1FC",
+    "764E3F4B53DD9DA1158BFD3E2B9C8CF56EDF019539349627DB2FD53D24B7C486",
+    "65772E437D6C7F8CE442734AF7CCB7AE837C264AE3A9BEB87F8A2FE9B8B5292E",
+    "5A021FFF5E91479E8CE7A28C2442C6F315180F93499A234DCF76E3FED135F9BB",
+);
+const N_2048: &str = concat!(
+    "AC6BDB41324A9A9BF166DE5E1389582FAF72B6651987EE07FC3192943DB56050",
+    "A37329CBB4A099ED8193E0757767A13DD52312AB4B03310DCD7F48A9DA04FD50",
+    "E8083969EDB767B0CF6095179A163AB3661A05FBD5FAAAE82918A9962F0B93B8",
+    "55F97993EC975EEAA80D740ADBF4FF747359D041D5C33EA71D281E446B14773B",
+    "CA97B43A23FB801676BD207A436C6481F1D2B9078717461A5B9D32E688F87748",
+    "544523B524B0D57D5EA77A2775D2ECFA032CFBDBF52FB3786160279004E57AE6",
+    "AF874E7303CE53299CCC041C7BC308D82A5698F3A8D0C38271AE35F8E9DBFBB6",
+    "94B5C803D89F7AE435DE236D525F54759B65E372FCD68EF20FA7111F9E4AFF73",
+);
+const N_3072: &str = concat!(
+    "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74",
+    "020BBEA63B139B22514A08798E3404DDEF9519B3CD3A431B302B0A6DF25F1437",
+    "4FE1356D6D51C245E485B576625E7EC6F44C42E9A637ED6B0BFF5CB6F406B7ED",
+    "EE386BFB5A899FA5AE9F24117C4B1FE649286651ECE45B3DC2007CB8A163BF05",
+    "98DA48361C55D39A69163FA8FD24CF5F83655D23DCA3AD961C62F356208552BB",
+    "9ED529077096966D670C354E4ABC9804F1746C08CA18217C32905E462E36CE3B",
+    "E39E772C180E86039B2783A2EC07A28FB5C55DF06F4C52C9DE2BCBF695581718",
+    "3995497CEA956AE515D2261898FA051015728E5A8AAAC42DAD33170D04507A33",
+    "A85521ABDF1CBA64ECFB850458DBEF0A8AEA71575D060C7DB3970F85A6E1E4C7",
+    "ABF5AE8CDB0933D71E8C94E04A25619DCEE3D2261AD2EE6BF12FFA06D98A0864",
+    "D87602733EC86A64521F2B18177B200CBBE117577A615D6C770988C0BAD946E2",
+    "08E24FA074E5AB3143DB5BFCE0FD108E4B82D120A93AD2CAFFFFFFFFFFFFFFFF",
+);
+const N_4096: &str = concat!(
+    "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74",
+    "020BBEA63B139B22514A08798E3404DDEF9519B3CD3A431B302B0A6DF25F1437",
+    "4FE1356D6D51C245E485B576625E7EC6F44C42E9A637ED6B0BFF5CB6F406B7ED",
+    "EE386BFB5A899FA5AE9F24117C4B1FE649286651ECE45B3DC2007CB8A163BF05",
+    "98DA48361C55D39A69163FA8FD24CF5F83655D23DCA3AD961C62F356208552BB",
+    "9ED529077096966D670C354E4ABC9804F1746C08CA18217C32905E462E36CE3B",
+    "E39E772C180E86039B2783A2EC07A28FB5C55DF06F4C52C9DE2BCBF695581718",
+    "3995497CEA956AE515D2261898FA051015728E5A8AAAC42DAD33170D04507A33",
+    "A85521ABDF1CBA64ECFB850458DBEF0A8AEA71575D060C7DB3970F85A6E1E4C7",
+    "ABF5AE8CDB0933D71E8C94E04A25619DCEE3D2261AD2EE6BF12FFA06D98A0864",
+    "D87602733EC86A64521F2B18177B200CBBE117577A615D6C770988C0BAD946E2",
+    "08E24FA074E5AB3143DB5BFCE0FD108E4B82D120A92108011A723C12A787E6D7",
+    "88719A10BDBA5B2699C327186AF4E23C1A946834B6150BDA2583E9CA2AD44CE8",
+    "DBBBC2DB04DE8EF92E8EFC141FBECAA6287C59474E6BC05D99B2964FA090C3A2",
+    "233BA186515BE7ED1F612970CEE2D7AFB81BDD762170481CD0069127D5B05AA9",
+    "93B4EA988D8FDDC186FFB7DC90A6C08F4DF435C934063199FFFFFFFFFFFFFFFF",
+);
+
+/// SRP group of RFC 5054 with a modulus of `bits` bits: 1024, 1536, 2048, 3072 or 4096.
+///
+/// The modulus is a safe prime `N = 2q + 1`; `g` is the generator assigned by the
+/// RFC, which need not lie in the subgroup of order `q`.
+pub fn srp_group(bits: usize) -> Result<ElgamalGroup> {
+    let (n, g) = match bits {
+        1024 => (N_1024, 2u32),
+        1536 => (N_1536, 2),
+        2048 => (N_2048, 2),
+        3072 => (N_3072, 5),
+        4096 => (N_4096, 5),
+        _ => return Err(Error::InvalidRange),
+    };
+    let n = BigUint::from_str_radix(n, 16).expect("valid constant");
+    let q = (&n - 1u32) >> 1;
+
+    Ok(ElgamalGroup::new(n, q, BigUint::from(g)))
+}
+
+fn hash(digest: &mut dyn DynDigest, parts: &[&[u8]]) -> Vec<u8> {
+    digest.reset();
+    for part in parts {
+        digest.update(part);
+    }
+
+    digest.finalize_reset().to_vec()
+}
+
+/// `PAD(n)`: big-endian on the size of `N`.
+fn pad(group: &ElgamalGroup, n: &BigUint) -> Vec<u8> {
+    group.element_to_bytes(n)
+}
+
+/// Private key `x = H(s | H(I | ":" | P))`.
+fn private_key(
+    digest: &mut dyn DynDigest,
+    username: &[u8],
+    password: &[u8],
+    salt: &[u8],
+) -> BigUint {
+    let inner = hash(digest, &[username, b":", password]);
+
+    BigUint::from_bytes_be(&hash(digest, &[salt, &inner]))
+}
+
+/// Multiplier `k = H(N | PAD(g))`.
+fn multiplier(digest: &mut dyn DynDigest, group: &ElgamalGroup) -> BigUint {
+    BigUint::from_bytes_be(&hash(
+        digest,
+        &[&group.get_p().to_bytes_be(), &pad(group, group.get_g())],
+    ))
+}
+
+/// Scrambling parameter `u = H(PAD(A) | PAD(B))`, never zero.
+fn scrambler(
+    digest: &mut dyn DynDigest,
+    group: &ElgamalGroup,
+    a: &BigUint,
+    b: &BigUint,
+) -> Result<BigUint> {
+    let u = BigUint::from_bytes_be(&hash(digest, &[&pad(group, a), &pad(group, b)]));
+    match u.is_zero() {
+        true => Err(Error::InvalidData),
+        false => Ok(u),
+    }
+}
+
+/// Session key `K = H(S)` with the evidence messages
+/// `M1 = H(H(N) XOR H(g) | H(I) | s | A | B | K)` and `M2 = H(A | M1 | K)`.
+fn session(
+    digest: &mut dyn DynDigest,
+    group: &ElgamalGroup,
+    username: &[u8],
+    salt: &[u8],
+    a: &BigUint,
+    b: &BigUint,
+    s: &BigUint,
+) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+    let key = hash(digest, &[&pad(group, s)]);
+
+    let hn = hash(digest, &[&group.get_p().to_bytes_be()]);
+    let hg = hash(digest, &[&group.get_g().to_bytes_be()]);
+    let hng: Vec<u8> = hn.iter().zip(&hg).map(|(x, y)| x ^ y).collect();
+    let hi = hash(digest, &[username]);
+    let (a, b) = (a.to_bytes_be(), b.to_bytes_be());
+    let m1 = hash(digest, &[&hng, &hi, salt, &a, &b, &key]);
+    let m2 = hash(digest, &[&a, &m1, &key]);
+
+    (key, m1, m2)
+}
+
+/// Checks that a public value is non-zero modulo `N`.
+fn check_public(group: &ElgamalGroup, value: &BigUint) -> Result<()> {
+    match (value % group.get_p()).is_zero() {
+        true => Err(Error::InvalidData),
+        false => Ok(()),
+    }
+}
+
+/// Verifier `v = g^x` stored by the server together with `salt`.
+pub fn srp_verifier(
+    digest: &mut dyn DynDigest,
+    group: &ElgamalGroup,
+    username: &[u8],
+    password: &[u8],
+    salt: &[u8],
+) -> BigUint {
+    let x = private_key(digest, username, password, salt);
+
+    group.get_g().modpow(&x, group.get_p())
+}
+
+/// First message, client to server: identity `I` and `A = g^a`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SrpClientHello {
+    pub username: Vec<u8>,
+    pub a: BigUint,
+}
+
+/// Server answer: salt `s` and `B = k v + g^b`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SrpServerHello {
+    pub salt: Vec<u8>,
+    pub b: BigUint,
+}
+
+/// Client evidence `M1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SrpClientProof {
+    pub m1: Vec<u8>,
+}
+
+/// Server evidence `M2`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SrpServerProof {
+    pub m2: Vec<u8>,
+}
+
+/// Client side of an SRP-6a exchange.
+#[derive(Debug, Clone)]
+pub struct SrpClient {
+    group: ElgamalGroup,
+    username: Vec<u8>,
+    a: BigUint,
+    big_a: BigUint,
+    /// `(K, M2)` once the server hello is processed
+    expected: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+/// Server side of an SRP-6a exchange.
+#[derive(Debug, Clone)]
+pub struct SrpServer {
+    key: Vec<u8>,
+    m1: Vec<u8>,
+    m2: Vec<u8>,
+}
+
+/// Random secret exponent of 256 bits at least, as required by RFC 5054.
+fn secret<R: RngCore + CryptoRng>(rng: &mut R, group: &ElgamalGroup) -> BigUint {
+    rng.gen_biguint_range(&BigUint::one(), group.get_q())
+}
+
+impl SrpClient {
+    /// Starts a login of `username`.
+    pub fn new<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        group: &ElgamalGroup,
+        username: &[u8],
+    ) -> (Self, SrpClientHello) {
+        Self::with_secret(group, username, secret(rng, group))
+    }
+
+    fn with_secret(group: &ElgamalGroup, username: &[u8], a: BigUint) -> (Self, SrpClientHello) {
+        let big_a = group.get_g().modpow(&a, group.get_p());
+
+        (
+            Self {
+                group: group.clone(),
+                username: username.to_vec(),
+                a,
+                big_a: big_a.clone(),
+                expected: None,
+            },
+            SrpClientHello {
+                username: username.to_vec(),
+                a: big_a,
+            },
+        )
+    }
+
+    /// Computes the premaster secret `S = (B - k g^x)^(a + u x)` and the evidence
+    /// proving knowledge of `password`.
+    pub fn respond(
+        &mut self,
+        digest: &mut dyn DynDigest,
+        password: &[u8],
+        hello: &SrpServerHello,
+    ) -> Result<SrpClientProof> {
+        let group = &self.group;
+        let n = group.get_p();
+        check_public(group, &hello.b)?;
+
+        let u = scrambler(digest, group, &self.big_a, &hello.b)?;
+        let k = multiplier(digest, group);
+        let x = private_key(digest, &self.username, password, &hello.salt);
+
+        let kgx = k * group.get_g().modpow(&x, n) % n;
+        let base = (&hello.b % n + n - kgx) % n;
+        let s = base.modpow(&(&self.a + u * x), n);
+
+        let (key, m1, m2) = session(
+            digest,
+            group,
+            &self.username,
+            &hello.salt,
+            &self.big_a,
+            &hello.b,
+            &s,
+        );
+        self.expected = Some((key, m2));
+
+        Ok(SrpClientProof { m1 })
+    }
+
+    /// Checks the server evidence and returns the session key `K`.
+    pub fn finish(&self, proof: &SrpServerProof) -> Result<Vec<u8>> {
+        let (key, m2) = self.expected.as_ref().ok_or(Error::InvalidState)?;
+        match bool::from(proof.m2.ct_eq(m2)) {
+            true => Ok(key.clone()),
+            false => Err(Error::Verification),
+        }
+    }
+}
+
+impl SrpServer {
+    /// Answers a client hello with the stored `salt` and `verifier` of the user,
+    /// deriving the premaster secret `S = (A v^u)^b`.
+    pub fn new<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        digest: &mut dyn DynDigest,
+        group: &ElgamalGroup,
+        hello: &SrpClientHello,
+        salt: &[u8],
+        verifier: &BigUint,
+    ) -> Result<(Self, SrpServerHello)> {
+        Self::with_secret(digest, group, hello, salt, verifier, secret(rng, group))
+    }
+
+    fn with_secret(
+        digest: &mut dyn DynDigest,
+        group: &ElgamalGroup,
+        hello: &SrpClientHello,
+        salt: &[u8],
+        verifier: &BigUint,
+        b: BigUint,
+    ) -> Result<(Self, SrpServerHello)> {
+        let n = group.get_p();
+        check_public(group, &hello.a)?;
+
+        let k = multiplier(digest, group);
+        let big_b = (k * verifier + group.get_g().modpow(&b, n)) % n;
+
+        let u = scrambler(digest, group, &hello.a, &big_b)?;
+        let s = (&hello.a * verifier.modpow(&u, n) % n).modpow(&b, n);
+        let (key, m1, m2) = session(digest, group, &hello.username, salt, &hello.a, &big_b, &s);
+
+        Ok((
+            Self { key, m1, m2 },
+            SrpServerHello {
+                salt: salt.to_vec(),
+                b: big_b,
+            },
+        ))
+    }
+
+    /// Checks the client evidence; returns the session key `K` and the server
+    /// evidence.
+    pub fn verify(&self, proof: &SrpClientProof) -> Result<(Vec<u8>, SrpServerProof)> {
+        match bool::from(proof.m1.ct_eq(&self.m1)) {
+            true => Ok((
+                self.key.clone(),
+                SrpServerProof {
+                    m2: self.m2.clone(),
+                },
+            )),
+            false => Err(Error::Verification),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{prelude::StdRng, SeedableRng};
+    use sha1::Sha1;
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+
+    fn hex(s: &str) -> BigUint {
+        BigUint::from_str_radix(s, 16).unwrap()
+    }
+
+    #[test]
+    fn srp_rfc5054_vectors() {
+        // RFC 5054 appendix B, 1024-bit group with SHA-1
+        let mut digest = Sha1::new();
+        let group = srp_group(1024).unwrap();
+        assert_eq!(group.get_p().bits(), 1024);
+        let salt = hex("BEB25379D1A8581EB5A727673A2441EE").to_bytes_be();
+
+        assert_eq!(
+            multiplier(&mut digest, &group),
+            hex("7556AA045AEF2CDD07ABAF0F665C3E818913186F")
+        );
+        assert_eq!(
+            private_key(&mut digest, b"alice", b"password123", &salt),
+            hex("94B7555AABE9127CC58CCF4993DB6CF84D16C124")
+        );
+        let verifier = srp_verifier(&mut digest, &group, b"alice", b"password123", &salt);
+        assert_eq!(
+            verifier,
+            hex(concat!(
+                "7E273DE8696FFC4F4E337D05B4B375BEB0DDE1569E8FA00A9886D8129BADA1F1",
+                "822223CA1A605B530E379BA4729FDC59F105B4787E5186F5C671085A1447B52A",
+                "48CF1970B4FB6F8400BBF4CEBFBB168152E08AB5EA53D15C1AFF87B2B9DA6E04",
+                "E058AD51CC72BFC9033B564E26480D78E955A5E29E7AB245DB2BE315E2099AFB",
+            ))
+        );
+
+        let a = hex("60975527035CF2AD1989806F0407210BC81EDC04E2762A56AFD529DDDA2D4393");
+        let (mut client, hello) = SrpClient::with_secret(&group, b"alice", a);
+        assert_eq!(
+            hello.a,
+            hex(concat!(
+                "61D5E490F6F1B79547B0704C436F523DD0E560F0C64115BB72557EC44352E890",
+                "3211C04692272D8B2D1A5358A2CF1B6E0BFCF99F921530EC8E39356179EAE45E",
+                "42BA92AEACED825171E1E8B9AF6D9C03E1327F44BE087EF06530E69F66615261",
+                "EEF54073CA11CF5858F0EDFDFE15EFEAB349EF5D76988A3672FAC47B0769447B",
+            ))
+        );
+
+        let b = hex("E487CB59D31AC550471E81F00F6928E01DDA08E974A004F49E61F5D105284D20");
+        let (server, reply) =
+            SrpServer::with_secret(&mut digest, &group, &hello, &salt, &verifier, b).unwrap();
+        assert_eq!(
+            reply.b,
+            hex(concat!(
+                "BD0C61512C692C0CB6D041FA01BB152D4916A1E77AF46AE105393011BAF38964",
+                "DC46A0670DD125B95A981652236F99D9B681CBF87837EC996C6DA04453728610",
+                "D0C6DDB58B318885D7D82C7F8DEB75CE7BD4FBAA37089E6F9C6059F388838E7A",
+                "00030B331EB76840910440B1B27AAEAEEB4012B7D7665238A8E3FB004B117B58",
+            ))
+        );
+        assert_eq!(
+            scrambler(&mut digest, &group, &hello.a, &reply.b).unwrap(),
+            hex("CE38B9593487DA98554ED47D70A7AE5F462EF019")
+        );
+
+        let premaster = hex(concat!(
+            "B0DC82BABCF30674AE450C0287745E7990A3381F63B387AAF271A10D233861E3",
+            "59B48220F7C4693C9AE12B0A6F67809F0876E2D013800D6C41BB59B6D5979B5C",
+            "00A172B4A2A5903A0BDCAF8A709585EB2AFAFA8F3499B200210DCC1F10EB3394",
+            "3CD67FC88A2F39A4BE5BEC4EC0A3212DC346D7E474B29EDE8A469FFECA686E5A",
+        ));
+        let expected = Sha1::digest(pad(&group, &premaster)).to_vec();
+
+        let proof = client.respond(&mut digest, b"password123", &reply).unwrap();
+        let (server_key, proof) = server.verify(&proof).unwrap();
+        assert_eq!(server_key, expected);
+        assert_eq!(client.finish(&proof).unwrap(), expected);
+
+        assert!(srp_group(512).is_err());
+    }
+
+    #[test]
+    fn srp_login() {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+        let group = srp_group(2048).unwrap();
+        let salt = b"random salt";
+        let verifier = srp_verifier(&mut digest, &group, b"alice", b"password123", salt);
+
+        let login = |rng: &mut StdRng, digest: &mut Sha256, password: &[u8]| {
+            let (mut client, hello) = SrpClient::new(rng, &group, b"alice");
+            let (server, reply) =
+                SrpServer::new(rng, digest, &group, &hello, salt, &verifier).unwrap();
+            let proof = client.respond(digest, password, &reply).unwrap();
+            let (server_key, proof) = server.verify(&proof)?;
+            let client_key = client.finish(&proof)?;
+            assert_eq!(client_key, server_key);
+            Ok::<_, Error>(client_key)
+        };
+
+        let k1 = login(&mut rng, &mut digest, b"password123").unwrap();
+        let k2 = login(&mut rng, &mut digest, b"password123").unwrap();
+        assert_ne!(k1, k2);
+        assert!(matches!(
+            login(&mut rng, &mut digest, b"password124"),
+            Err(Error::Verification)
+        ));
+
+        let (_, hello) = SrpClient::new(&mut rng, &group, b"alice");
+        let zero = SrpClientHello {
+            a: group.get_p().clone(),
+            ..hello
+        };
+        assert!(SrpServer::new(&mut rng, &mut digest, &group, &zero, salt, &verifier).is_err());
+    }
+}
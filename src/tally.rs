@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+
+use digest::DynDigest;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::{Error, Result};
+use crate::group::Group;
+use crate::internal::{group_ciphertext_mul, group_decrypt};
+use crate::proofs::DecryptionProof;
+
+/// Largest `bound` accepted by [`solve_dlog`].
+pub const MAX_DLOG_BOUND: u64 = 1 << 40;
+
+/// Discrete logarithm of `h` in base `g`, searched in `[0, bound]` with baby-step
+/// giant-step in `O(sqrt(bound))` time and memory.
+///
+/// The table of baby steps holds `sqrt(bound) + 1` encoded elements: at the cap of
+/// [`MAX_DLOG_BOUND`] that is about 2^20 entries, around 100 MiB with Ristretto255
+/// and 600 MiB with 4096-bit integers. Larger bounds fail with
+/// [`Error::InvalidRange`].
+pub fn solve_dlog<G: Group>(group: &G, h: &G::Element, bound: u64) -> Result<u64> {
+    if bound > MAX_DLOG_BOUND {
+        return Err(Error::InvalidRange);
+    }
+
+    let m = ((bound as f64).sqrt() as u64).saturating_add(1);
+
+    // Baby steps: g^j for j in [0, m).
+    let mut table = HashMap::with_capacity(m as usize);
+    let mut baby = group.identity();
+    for j in 0..m {
+        table.entry(group.element_to_bytes(&baby)).or_insert(j);
+        baby = group.mul(&baby, &group.generator());
+    }
+
+    // Giant steps: h * g^(-m i).
+    let giant = group
+        .invert(&group.exp_generator(&group.scalar_from_u64(m)))
+        .ok_or(Error::InvalidInverse)?;
+    let mut current = h.clone();
+    for i in 0..=bound / m {
+        if let Some(j) = table.get(&group.element_to_bytes(&current)) {
+            let v = i * m + j;
+            if v <= bound {
+                return Ok(v);
+            }
+        }
+        current = group.mul(&current, &giant);
+    }
+
+    Err(Error::InvalidRange)
+}
+
+/// Homomorphic accumulator of vectors of exponential El Gamal ciphertexts, one
+/// counter per candidate or bucket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tally<G: Group> {
+    group: G,
+    /// Public key the counters are encrypted under
+    y: G::Element,
+    /// Encrypted sum of every counter
+    totals: Vec<(G::Element, G::Element)>,
+    /// Number of vectors accumulated
+    count: u64,
+}
+
+impl<G: Group> Tally<G> {
+    /// Empty tally of `size` counters, all encrypting zero.
+    pub fn new(group: &G, y: G::Element, size: usize) -> Self {
+        Self {
+            group: group.clone(),
+            y,
+            totals: vec![(group.identity(), group.identity()); size],
+            count: 0,
+        }
+    }
+
+    /// Returns the encrypted totals.
+    pub fn totals(&self) -> &[(G::Element, G::Element)] {
+        &self.totals
+    }
+
+    /// Returns the number of vectors accumulated.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Adds a vector of encrypted counters, one per bucket.
+    pub fn add(&mut self, ciphertexts: &[(G::Element, G::Element)]) -> Result<()> {
+        if ciphertexts.len() != self.totals.len() {
+            return Err(Error::InvalidData);
+        }
+        let group = &self.group;
+        if ciphertexts
+            .iter()
+            .any(|(a, b)| !group.is_element(a) || !group.is_element(b))
+        {
+            return Err(Error::InvalidData);
+        }
+
+        for (total, (a, b)) in self.totals.iter_mut().zip(ciphertexts) {
+            *total = group_ciphertext_mul(group, (&total.0, &total.1), (a, b));
+        }
+        self.count += 1;
+
+        Ok(())
+    }
+
+    /// Decrypts every total with the private key `x`; each must be at most `bound`.
+    pub fn decrypt(&self, x: &G::Scalar, bound: u64) -> Result<Vec<u64>> {
+        self.totals
+            .iter()
+            .map(|(a, b)| {
+                let m = group_decrypt(&self.group, x, a, b)?;
+                solve_dlog(&self.group, &m, bound)
+            })
+            .collect()
+    }
+
+    /// Decrypts every total and proves each result correct.
+    pub fn decrypt_with_proof<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        digest: &mut dyn DynDigest,
+        x: &G::Scalar,
+        bound: u64,
+    ) -> Result<Vec<(u64, DecryptionProof<G>)>> {
+        self.totals
+            .iter()
+            .map(|(a, b)| {
+                let (m, proof) = DecryptionProof::prove(rng, &self.group, digest, x, a, b)?;
+                Ok((solve_dlog(&self.group, &m, bound)?, proof))
+            })
+            .collect()
+    }
+
+    /// Verifies published results against the encrypted totals.
+    pub fn verify(
+        &self,
+        digest: &mut dyn DynDigest,
+        results: &[(u64, DecryptionProof<G>)],
+    ) -> Result<()> {
+        if results.len() != self.totals.len() {
+            return Err(Error::InvalidData);
+        }
+
+        for ((a, b), (v, proof)) in self.totals.iter().zip(results) {
+            let m = self.group.exp_generator(&self.group.scalar_from_u64(*v));
+            proof.verify(&self.group, digest, &self.y, a, b, &m)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{prelude::StdRng, SeedableRng};
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::internal::group_encrypt_exponent_with;
+    use crate::{ElgamalGroup, Ristretto255};
+
+    #[test]
+    fn dlog() {
+        let group = Ristretto255;
+        for v in [0, 1, 99, 100, 1234] {
+            let h = group.exp_generator(&group.scalar_from_u64(v));
+            assert_eq!(solve_dlog(&group, &h, 1234).unwrap(), v);
+        }
+
+        let h = group.exp_generator(&group.scalar_from_u64(1235));
+        assert!(solve_dlog(&group, &h, 1234).is_err());
+        assert!(matches!(
+            solve_dlog(&group, &h, MAX_DLOG_BOUND + 1),
+            Err(Error::InvalidRange)
+        ));
+    }
+
+    fn tally<G: Group>(group: &G) {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+        let x = group.random_scalar(&mut rng);
+        let y = group.exp_generator(&x);
+
+        let votes = [[1, 0, 0], [0, 1, 0], [1, 0, 0], [0, 0, 1], [1, 0, 0]];
+        let mut tally = Tally::new(group, y.clone(), 3);
+        for vote in votes.iter() {
+            let ciphertexts: Vec<_> = vote
+                .iter()
+                .map(|v| {
+                    let r = group.random_scalar(&mut rng);
+                    group_encrypt_exponent_with(group, &y, &group.scalar_from_u64(*v), &r)
+                })
+                .collect();
+            tally.add(&ciphertexts).unwrap();
+        }
+
+        assert_eq!(tally.count(), 5);
+        assert_eq!(tally.decrypt(&x, tally.count()).unwrap(), vec![3, 1, 1]);
+
+        let mut results = tally
+            .decrypt_with_proof(&mut rng, &mut digest, &x, tally.count())
+            .unwrap();
+        tally.verify(&mut digest, &results).unwrap();
+        results[0].0 = 2;
+        assert!(tally.verify(&mut digest, &results).is_err());
+    }
+
+    #[test]
+    fn homomorphic_tally() {
+        let mut rng = StdRng::from_entropy();
+        tally(&ElgamalGroup::generate(&mut rng, 256, 128));
+        tally(&Ristretto255);
+    }
+}
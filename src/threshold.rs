@@ -0,0 +1,288 @@
+use digest::DynDigest;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::error::{Error, Result};
+use crate::group::Group;
+use crate::proofs::DleqProof;
+use crate::sharing::{commitments_evaluate, lagrange_coefficient};
+use crate::{ElgamalGroup, ElgamalPublicKey};
+
+/// Public part of a t-of-n shared El Gamal key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThresholdPublicKey<G: Group> {
+    /// Group of the key
+    group: G,
+    /// Number of shares required to use the key
+    threshold: usize,
+    /// Feldman commitments to the sharing polynomial, `commitments[0] = y`
+    commitments: Vec<G::Element>,
+}
+
+/// Share of a t-of-n shared El Gamal key held by a single party.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThresholdKeyShare<G: Group> {
+    /// Index of the party, starting from 1
+    index: u32,
+    /// x_i = f(i)
+    pub(crate) share: G::Scalar,
+    /// Public part of the key
+    public: ThresholdPublicKey<G>,
+}
+
+/// Partial decryption `d_i = a^x_i` of a ciphertext by a single trustee.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecryptionShare<G: Group> {
+    /// Index of the trustee
+    pub index: u32,
+    /// d_i = a^x_i
+    pub d: G::Element,
+    /// Proof that `log_g g^x_i = log_a d_i`
+    pub proof: DleqProof<G>,
+}
+
+impl<G: Group> ThresholdPublicKey<G> {
+    /// Builds the key from the commitments to the sharing polynomial, which must all be
+    /// group elements; the joint key `commitments[0]` must not be the identity.
+    pub fn new(group: G, commitments: Vec<G::Element>) -> Result<Self> {
+        match commitments.first() {
+            Some(y) if *y != group.identity() => {}
+            _ => return Err(Error::InvalidData),
+        }
+        if commitments.iter().any(|c| !group.is_element(c)) {
+            return Err(Error::InvalidData);
+        }
+
+        Ok(Self {
+            group,
+            threshold: commitments.len(),
+            commitments,
+        })
+    }
+
+    /// Returns the group of the key.
+    pub fn group(&self) -> &G {
+        &self.group
+    }
+
+    /// Returns the number of shares required to use the key.
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Returns the commitments to the sharing polynomial.
+    pub fn commitments(&self) -> &[G::Element] {
+        &self.commitments
+    }
+
+    /// Returns the joint public key `y = g^x`.
+    pub fn get_y(&self) -> &G::Element {
+        &self.commitments[0]
+    }
+
+    /// Returns `g^x_i`, the public counterpart of the share of party `index`.
+    pub fn verification_key(&self, index: u32) -> G::Element {
+        commitments_evaluate(&self.group, &self.commitments, index)
+    }
+}
+
+impl<G: Group> ThresholdPublicKey<G> {
+    /// Verifies the partial decryption of `a` produced by a trustee.
+    pub fn verify_decryption_share(
+        &self,
+        digest: &mut dyn DynDigest,
+        a: &G::Element,
+        share: &DecryptionShare<G>,
+    ) -> Result<()> {
+        if share.index == 0 || !self.group.is_element(a) || !self.group.is_element(&share.d) {
+            return Err(Error::InvalidData);
+        }
+
+        share.proof.verify(
+            &self.group,
+            digest,
+            &self.group.generator(),
+            &self.verification_key(share.index),
+            a,
+            &share.d,
+        )
+    }
+
+    /// Recovers the plaintext of `(a, b)` from the partial decryptions of at least
+    /// `threshold` trustees. Every share is verified: invalid shares and repeated
+    /// indices are skipped, and the first `threshold` valid shares are used.
+    pub fn combine(
+        &self,
+        digest: &mut dyn DynDigest,
+        a: &G::Element,
+        b: &G::Element,
+        shares: &[DecryptionShare<G>],
+    ) -> Result<G::Element> {
+        let mut valid: Vec<&DecryptionShare<G>> = Vec::with_capacity(self.threshold);
+        for share in shares {
+            if valid.len() == self.threshold {
+                break;
+            }
+            if valid.iter().any(|other| other.index == share.index)
+                || self.verify_decryption_share(digest, a, share).is_err()
+            {
+                continue;
+            }
+            valid.push(share);
+        }
+        if valid.len() < self.threshold {
+            return Err(Error::NotEnoughShares);
+        }
+
+        let indices: Vec<u32> = valid.iter().map(|share| share.index).collect();
+        let mut ax = self.group.identity();
+        for share in valid {
+            let lambda = lagrange_coefficient(&self.group, share.index, &indices)?;
+            ax = self.group.mul(&ax, &self.group.exp(&share.d, &lambda));
+        }
+
+        let divider = self.group.invert(&ax).ok_or(Error::InvalidInverse)?;
+        Ok(self.group.mul(b, &divider))
+    }
+}
+
+impl ThresholdPublicKey<ElgamalGroup> {
+    /// Returns the joint public key usable with the regular encryption functions.
+    pub fn to_public_key(&self) -> ElgamalPublicKey {
+        ElgamalPublicKey::new(self.group.clone(), self.get_y().clone())
+    }
+}
+
+impl<G: Group> ThresholdKeyShare<G> {
+    /// Builds a share, checking it against the public commitments.
+    pub fn new(index: u32, share: G::Scalar, public: ThresholdPublicKey<G>) -> Result<Self> {
+        if index == 0 || public.group.exp_generator(&share) != public.verification_key(index) {
+            return Err(Error::InvalidShare);
+        }
+
+        Ok(Self {
+            index,
+            share,
+            public,
+        })
+    }
+
+    /// Returns the index of the share.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns the secret share `x_i`.
+    pub fn get_share(&self) -> &G::Scalar {
+        &self.share
+    }
+
+    /// Returns the public part of the key.
+    pub fn public(&self) -> &ThresholdPublicKey<G> {
+        &self.public
+    }
+}
+
+impl<G: Group> ThresholdKeyShare<G> {
+    /// Partial decryption of a ciphertext with first component `a`, with a proof of correctness.
+    pub fn decryption_share<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        digest: &mut dyn DynDigest,
+        a: &G::Element,
+    ) -> Result<DecryptionShare<G>> {
+        let group = &self.public.group;
+        if !group.is_element(a) {
+            return Err(Error::InvalidData);
+        }
+
+        Ok(DecryptionShare {
+            index: self.index,
+            d: group.exp(a, &self.share),
+            proof: DleqProof::prove(rng, group, digest, &group.generator(), a, &self.share),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{prelude::StdRng, SeedableRng};
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::internal::group_encrypt;
+    use crate::keys::ElgamalGroupElements;
+    use crate::sharing::Polynomial;
+
+    /// Shares of a key dealt by a trusted dealer.
+    fn deal<G: Group>(
+        rng: &mut StdRng,
+        group: &G,
+        threshold: usize,
+        parties: u32,
+    ) -> Vec<ThresholdKeyShare<G>> {
+        let secret = group.random_scalar(rng);
+        let polynomial = Polynomial::random(rng, group, secret, threshold);
+        let public = ThresholdPublicKey::new(group.clone(), polynomial.commitments(group)).unwrap();
+
+        (1..=parties)
+            .map(|index| {
+                ThresholdKeyShare::new(index, polynomial.evaluate(group, index), public.clone())
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn threshold_decrypt() {
+        let mut rng = StdRng::from_entropy();
+        let mut digest = Sha256::new();
+        let group = ElgamalGroup::generate(&mut rng, 256, 128);
+        let shares = deal(&mut rng, &group, 3, 5);
+        let public = shares[0].public().clone();
+
+        let m = group.exp_generator(&group.random_scalar(&mut rng));
+        let (a, b) = group_encrypt(&mut rng, &group, public.get_y(), &m);
+
+        let decryption_shares: Vec<_> = shares[1..]
+            .iter()
+            .map(|share| share.decryption_share(&mut rng, &mut digest, &a).unwrap())
+            .collect();
+
+        assert_eq!(
+            public
+                .combine(&mut digest, &a, &b, &decryption_shares)
+                .unwrap(),
+            m
+        );
+        assert_eq!(
+            public
+                .combine(&mut digest, &a, &b, &decryption_shares[1..])
+                .unwrap(),
+            m
+        );
+        assert!(matches!(
+            public.combine(&mut digest, &a, &b, &decryption_shares[2..]),
+            Err(Error::NotEnoughShares)
+        ));
+
+        let mut commitments = public.commitments().to_vec();
+        commitments[1] = group.get_p().clone();
+        assert!(ThresholdPublicKey::new(group.clone(), commitments.clone()).is_err());
+        commitments[1] = group.get_p() - 1u32;
+        assert!(ThresholdPublicKey::new(group.clone(), commitments.clone()).is_err());
+        commitments[0] = group.identity();
+        commitments[1] = public.commitments()[1].clone();
+        assert!(ThresholdPublicKey::new(group.clone(), commitments).is_err());
+
+        // Invalid and repeated shares are skipped as long as enough valid ones remain.
+        let mut forged = decryption_shares.clone();
+        forged[0].d = group.mul(&forged[0].d, &group.generator());
+        assert_eq!(public.combine(&mut digest, &a, &b, &forged).unwrap(), m);
+        forged.insert(1, decryption_shares[1].clone());
+        assert_eq!(public.combine(&mut digest, &a, &b, &forged).unwrap(), m);
+        assert!(matches!(
+            public.combine(&mut digest, &a, &b, &forged[..4]),
+            Err(Error::NotEnoughShares)
+        ));
+    }
+}
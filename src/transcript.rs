@@ -0,0 +1,111 @@
+use digest::DynDigest;
+use num_bigint::BigUint;
+
+use crate::group::Group;
+
+/// Fiat–Shamir transcript over any hash function.
+///
+/// Every message is absorbed together with a label, both length-prefixed, so that
+/// different sequences of messages never hash to the same input. Challenges are
+/// squeezed with 128 extra bits and reduced modulo `q`, which keeps their bias
+/// negligible, and are absorbed back so that later challenges depend on them.
+pub struct Transcript<'a> {
+    digest: &'a mut dyn DynDigest,
+}
+
+/// Bytes added to the size of the order of the group before reducing a challenge.
+const WIDE_REDUCTION_BYTES: usize = 16;
+
+impl<'a> Transcript<'a> {
+    /// Starts a transcript for the protocol identified by `label`.
+    pub fn new(digest: &'a mut dyn DynDigest, label: &[u8]) -> Self {
+        digest.reset();
+        let mut transcript = Self { digest };
+        transcript.append_message(b"protocol", label);
+
+        transcript
+    }
+
+    /// Absorbs raw bytes.
+    pub fn append_message(&mut self, label: &[u8], message: &[u8]) {
+        self.digest.update(&(label.len() as u64).to_be_bytes());
+        self.digest.update(label);
+        self.digest.update(&(message.len() as u64).to_be_bytes());
+        self.digest.update(message);
+    }
+
+    /// Absorbs a group element in its fixed-width encoding.
+    pub fn append_element<G: Group>(&mut self, group: &G, label: &[u8], element: &G::Element) {
+        self.append_message(label, &group.element_to_bytes(element));
+    }
+
+    /// Absorbs a scalar in its fixed-width encoding.
+    pub fn append_scalar<G: Group>(&mut self, group: &G, label: &[u8], scalar: &G::Scalar) {
+        self.append_message(label, &group.scalar_to_bytes(scalar));
+    }
+
+    /// Squeezes `size` bytes bound to everything absorbed so far.
+    pub fn challenge_bytes(&mut self, label: &[u8], size: usize) -> Vec<u8> {
+        self.append_message(b"challenge", label);
+        let seed = self.digest.finalize_reset();
+
+        let mut output = Vec::with_capacity(size + seed.len());
+        let mut counter = 0u32;
+        while output.len() < size {
+            self.digest.update(&seed);
+            self.digest.update(&counter.to_be_bytes());
+            output.extend_from_slice(&self.digest.finalize_reset());
+            counter += 1;
+        }
+        output.truncate(size);
+
+        // Chain the state: the next challenge depends on this one.
+        self.digest.update(&seed);
+
+        output
+    }
+
+    /// Squeezes a challenge modulo the order of `group`.
+    pub fn challenge_scalar<G: Group>(&mut self, group: &G, label: &[u8]) -> G::Scalar {
+        let size = (group.order().bits() + 7) / 8 + WIDE_REDUCTION_BYTES;
+        let bytes = self.challenge_bytes(label, size);
+
+        group.scalar_from_biguint(&BigUint::from_bytes_be(&bytes))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::Ristretto255;
+
+    #[test]
+    fn transcript_separation() {
+        let group = Ristretto255;
+        let mut digest = Sha256::new();
+        let g = group.generator();
+
+        let mut challenge = |label: &[u8], messages: &[(&[u8], &[u8])]| {
+            let mut transcript = Transcript::new(&mut digest, b"test");
+            for (label, message) in messages {
+                transcript.append_message(label, message);
+            }
+            transcript.append_element(&group, b"g", &g);
+            transcript.challenge_scalar(&group, label)
+        };
+
+        let c = challenge(b"c", &[(b"a", b"bc")]);
+        assert_eq!(c, challenge(b"c", &[(b"a", b"bc")]));
+        assert_ne!(c, challenge(b"c", &[(b"ab", b"c")]));
+        assert_ne!(c, challenge(b"c", &[(b"a", b"b"), (b"", b"c")]));
+        assert_ne!(c, challenge(b"d", &[(b"a", b"bc")]));
+
+        // Successive challenges differ.
+        let mut transcript = Transcript::new(&mut digest, b"test");
+        let c1: <Ristretto255 as Group>::Scalar = transcript.challenge_scalar(&group, b"c");
+        let c2 = transcript.challenge_scalar(&group, b"c");
+        assert_ne!(c1, c2);
+    }
+}